@@ -0,0 +1,178 @@
+//! Asynchronous driver.
+
+use crate::{BufferedUart, InterruptController, InterruptEvents, Mmio, RegisterAccess, Uart};
+use core::cell::UnsafeCell;
+use core::future::poll_fn;
+use core::sync::atomic::{AtomicU8, Ordering};
+use core::task::{Poll, Waker};
+
+/// A waker is being registered
+const REGISTERING: u8 = 0b01;
+/// The registered waker is being woken
+const WAKING: u8 = 0b10;
+
+/// Slot holding the waker of the task waiting for an event, which can be woken from an
+/// interrupt handler without blocking
+struct WakerSlot {
+	state: AtomicU8,
+	waker: UnsafeCell<Option<Waker>>,
+}
+
+// The waker is only accessed by the context which set the corresponding state bit.
+unsafe impl Sync for WakerSlot {}
+
+impl WakerSlot {
+	const fn new() -> Self {
+		Self {
+			state: AtomicU8::new(0),
+			waker: UnsafeCell::new(None),
+		}
+	}
+
+	/// Registers `waker` to be woken by the next call to [`wake`](Self::wake).
+	fn register(&self, waker: &Waker) {
+		match self
+			.state
+			.compare_exchange(0, REGISTERING, Ordering::Acquire, Ordering::Acquire)
+		{
+			Ok(_) => {
+				unsafe {
+					match &mut *self.waker.get() {
+						Some(registered) if registered.will_wake(waker) => {}
+						slot => *slot = Some(waker.clone()),
+					}
+				}
+				if self
+					.state
+					.compare_exchange(REGISTERING, 0, Ordering::AcqRel, Ordering::Acquire)
+					.is_err()
+				{
+					// Woken while registering
+					let waker = unsafe { (*self.waker.get()).take() };
+					self.state.store(0, Ordering::Release);
+					if let Some(waker) = waker {
+						waker.wake();
+					}
+				}
+			}
+			// Being woken, so the event already occurred
+			Err(WAKING) => waker.wake_by_ref(),
+			Err(_) => {}
+		}
+	}
+
+	/// Wakes the registered waker, if any.
+	fn wake(&self) {
+		if self.state.fetch_or(WAKING, Ordering::AcqRel) == 0 {
+			let waker = unsafe { (*self.waker.get()).take() };
+			self.state.fetch_and(!WAKING, Ordering::Release);
+			if let Some(waker) = waker {
+				waker.wake();
+			}
+		}
+	}
+}
+
+/// UART peripheral with asynchronous reads and writes, driven by the interrupt handler
+///
+/// [`handle_interrupt`](Self::handle_interrupt) must be called from the interrupt handler of
+/// the peripheral, and wakes the tasks waiting in [`read`](Self::read) or
+/// [`write`](Self::write). Each of them must be used by a single task at a time. The buffers
+/// are sized like those of [`BufferedUart`], and the transmission likewise ignores the RS-485
+/// setting of [`Uart`].
+pub struct AsyncUart<R = Mmio, const RX: usize = 256, const TX: usize = 256> {
+	inner: BufferedUart<R, RX, TX>,
+	rx_waker: WakerSlot,
+	tx_waker: WakerSlot,
+}
+
+impl<R: RegisterAccess, const RX: usize, const TX: usize> AsyncUart<R, RX, TX> {
+	/// Creates a new instance of `AsyncUart` using `uart`, which must be initialized.
+	pub const fn new(uart: Uart<R>) -> Self {
+		Self {
+			inner: BufferedUart::new(uart),
+			rx_waker: WakerSlot::new(),
+			tx_waker: WakerSlot::new(),
+		}
+	}
+
+	/// Returns the underlying UART peripheral
+	pub fn uart(&self) -> &Uart<R> {
+		self.inner.uart()
+	}
+
+	/// Returns the underlying UART peripheral mutably, e.g. to change its settings or to
+	/// [`rebase`](Uart::rebase) it once the MMU maps it at another address.
+	pub fn uart_mut(&mut self) -> &mut Uart<R> {
+		self.inner.uart_mut()
+	}
+
+	/// See [`BufferedUart::set_interrupt_controller`].
+	pub fn set_interrupt_controller(&mut self, irq: Option<&'static dyn InterruptController>) {
+		self.inner.set_interrupt_controller(irq);
+	}
+
+	/// Enables the interrupts like [`BufferedUart::enable`].
+	pub fn enable(&self) {
+		self.inner.enable();
+	}
+
+	/// Handles the pending interrupts like [`BufferedUart::handle_interrupt`], waking the task
+	/// waiting for received data if any is available, and the task waiting to write if the
+	/// transmitter holding register is empty.
+	pub fn handle_interrupt(&self) -> InterruptEvents {
+		let events = self.inner.handle_interrupt();
+		if self.inner.bytes_available() != 0 {
+			self.rx_waker.wake();
+		}
+		if events.tx_empty {
+			self.tx_waker.wake();
+		}
+		events
+	}
+
+	/// Waits until received data is available, moves it to `buf`, and returns the number of
+	/// bytes read.
+	pub async fn read(&self, buf: &mut [u8]) -> usize {
+		poll_fn(|cx| {
+			let read = self.inner.read(buf);
+			if read != 0 || buf.is_empty() {
+				return Poll::Ready(read);
+			}
+			self.rx_waker.register(cx.waker());
+			// Data may have been received before the waker was registered
+			match self.inner.read(buf) {
+				0 => Poll::Pending,
+				read => Poll::Ready(read),
+			}
+		})
+		.await
+	}
+
+	/// Waits until there is space in the transmit buffer, moves bytes from `buf` to it, and
+	/// returns the number of bytes written.
+	pub async fn write(&self, buf: &[u8]) -> usize {
+		poll_fn(|cx| {
+			let written = self.inner.write(buf);
+			if written != 0 || buf.is_empty() {
+				return Poll::Ready(written);
+			}
+			self.tx_waker.register(cx.waker());
+			// Space may have been freed before the waker was registered
+			match self.inner.write(buf) {
+				0 => Poll::Pending,
+				written => Poll::Ready(written),
+			}
+		})
+		.await
+	}
+
+	/// Writes all of `buf`, waiting for space in the transmit buffer as needed. Like with
+	/// [`write`](Self::write), the bytes are written as is, without newline translation.
+	pub async fn write_all(&self, mut buf: &[u8]) {
+		while !buf.is_empty() {
+			let written = self.write(buf).await;
+			buf = &buf[written..];
+		}
+	}
+}
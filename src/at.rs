@@ -0,0 +1,72 @@
+//! Zero-sized UART at a base address known at compile time.
+
+use crate::{Config, MmioAt, Uart};
+use core::fmt::{Result, Write};
+
+#[derive(Debug)]
+/// UART peripheral at the base address `BASE`, with the registers spaced by `1 << REG_SHIFT`
+/// bytes and accessed with byte wide reads and writes, which stores no state
+///
+/// `UartAt` is zero-sized and the addresses of the registers are constants, so it can be a
+/// `static` in a size-constrained program. As no state is stored, each operation uses a
+/// [`Uart`] with the default settings: the FIFO configuration is not remembered, so the bytes
+/// are written one at a time, and the settings of [`Uart`], e.g. the newline translation, are
+/// not available. A [`Uart`] accessing the same registers, created with [`Uart::at`], stores
+/// them.
+pub struct UartAt<const BASE: usize, const REG_SHIFT: u8 = 0>;
+
+impl<const BASE: usize, const REG_SHIFT: u8> UartAt<BASE, REG_SHIFT> {
+	/// Creates a new instance of `UartAt`.
+	///
+	/// # Safety
+	///
+	/// See [`MmioAt::new`].
+	pub const unsafe fn new() -> Self {
+		Self
+	}
+
+	fn uart(&self) -> Uart<MmioAt<BASE, REG_SHIFT>> {
+		unsafe { Uart::at() }
+	}
+
+	/// See [`Uart::init_with`].
+	pub fn init_with(&self, config: &Config) {
+		self.uart().init_with(config);
+	}
+
+	/// See [`Uart::put`].
+	pub fn put(&self, c: u8) -> Option<u8> {
+		self.uart().put(c)
+	}
+
+	/// See [`Uart::put_blocking`].
+	pub fn put_blocking(&self, c: u8) {
+		self.uart().put_blocking(c);
+	}
+
+	/// See [`Uart::write_bytes`].
+	pub fn write_bytes(&self, bytes: &[u8]) -> usize {
+		self.uart().write_bytes(bytes)
+	}
+
+	/// See [`Uart::flush`].
+	pub fn flush(&self) {
+		self.uart().flush();
+	}
+
+	/// See [`Uart::get`].
+	pub fn get(&self) -> Option<u8> {
+		self.uart().get()
+	}
+
+	/// See [`Uart::get_blocking`].
+	pub fn get_blocking(&self) -> u8 {
+		self.uart().get_blocking()
+	}
+}
+
+impl<const BASE: usize, const REG_SHIFT: u8> Write for UartAt<BASE, REG_SHIFT> {
+	fn write_str(&mut self, s: &str) -> Result {
+		self.uart().write_text(s)
+	}
+}
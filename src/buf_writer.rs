@@ -0,0 +1,69 @@
+//! Buffered writer.
+
+use crate::{RegisterAccess, Uart};
+use core::fmt::{Error, Result, Write};
+
+#[derive(Debug)]
+/// Writer accumulating formatted output in a buffer of `N` bytes, and writing it to the
+/// peripheral with [`Uart::write_bytes`] in bursts filling the transmitter FIFO
+///
+/// The buffer must have at least 1 byte.
+///
+/// The buffer is written when it is full, when [`flush`](Self::flush) is called, and when the
+/// writer is dropped. The newline translation is applied while buffering.
+pub struct BufWriter<'a, R: RegisterAccess, const N: usize = 64> {
+	uart: &'a Uart<R>,
+	buffer: [u8; N],
+	len: usize,
+}
+
+impl<'a, R: RegisterAccess, const N: usize> BufWriter<'a, R, N> {
+	/// Creates a new instance of `BufWriter` writing to `uart`.
+	pub const fn new(uart: &'a Uart<R>) -> Self {
+		assert!(N >= 1, "a buffered writer needs at least 1 byte");
+		Self {
+			uart,
+			buffer: [0; N],
+			len: 0,
+		}
+	}
+
+	/// Writes the buffered bytes, and returns an error if the write was aborted by the hook set
+	/// with [`Uart::set_write_hook`], in which case the buffer is discarded.
+	pub fn flush(&mut self) -> Result {
+		let len = self.len;
+		self.len = 0;
+		if self.uart.write_bytes(&self.buffer[..len]) == len {
+			Ok(())
+		} else {
+			Err(Error)
+		}
+	}
+
+	fn push(&mut self, c: u8) -> Result {
+		if self.len == N {
+			self.flush()?;
+		}
+		self.buffer[self.len] = c;
+		self.len += 1;
+		Ok(())
+	}
+}
+
+impl<R: RegisterAccess, const N: usize> Write for BufWriter<'_, R, N> {
+	fn write_str(&mut self, s: &str) -> Result {
+		for &c in s.as_bytes() {
+			if c == b'\n' && self.uart.newline.lf_to_crlf {
+				self.push(b'\r')?;
+			}
+			self.push(c)?;
+		}
+		Ok(())
+	}
+}
+
+impl<R: RegisterAccess, const N: usize> Drop for BufWriter<'_, R, N> {
+	fn drop(&mut self) {
+		let _ = self.flush();
+	}
+}
@@ -0,0 +1,392 @@
+//! Interrupt-driven buffered driver.
+
+use crate::ring::RingBuffer;
+use crate::stats::Counter;
+use crate::{
+	InterruptController, InterruptEnable, InterruptEvents, Mmio, ModemStatus, RegisterAccess, Uart,
+};
+use core::fmt::{Result, Write};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Character resuming the transmission with software flow control
+const XON: u8 = 0x11;
+/// Character pausing the transmission with software flow control
+const XOFF: u8 = 0x13;
+/// Number of modem status changes that can be queued, plus one
+const MODEM_EVENTS: usize = 16;
+
+/// UART peripheral whose received and transmitted data is buffered and moved from the interrupt
+/// handler
+///
+/// The receive and transmit buffers have `RX` and `TX` slots respectively, 256 by default, and
+/// hold one byte less. Both must have at least 2 slots.
+///
+/// [`handle_interrupt`](Self::handle_interrupt) must be called from the interrupt handler of
+/// the peripheral, [`read`](Self::read) from a single context and [`write`](Self::write) from a
+/// single context. The transmission ignores the RS-485 setting of [`Uart`].
+pub struct BufferedUart<R = Mmio, const RX: usize = 256, const TX: usize = 256> {
+	uart: Uart<R>,
+	rx: RingBuffer<RX>,
+	tx: RingBuffer<TX>,
+	/// Modem status changes not drained yet, as modem status register values
+	modem_events: RingBuffer<MODEM_EVENTS>,
+	/// Whether the modem status changes are queued
+	queue_modem_events: bool,
+	/// Whether XON/XOFF software flow control is enabled
+	xon_xoff: bool,
+	/// Whether driver-managed RTS/CTS hardware flow control is enabled
+	rts_cts: bool,
+	/// Whether XOFF was received and not followed by XON
+	tx_paused: AtomicBool,
+	/// Last state of the clear to send line
+	cts: AtomicBool,
+	/// Whether the remote was asked to pause the transmission and not to resume it
+	rx_paused: AtomicBool,
+	/// Whether XOFF if `rx_paused` is set, XON otherwise, must be sent
+	control: AtomicBool,
+	/// Whether request to send must be deasserted if `rx_paused` is set, asserted otherwise
+	rts_update: AtomicBool,
+	/// Interrupt controller called when enabling and handling the interrupts
+	irq: Option<&'static dyn InterruptController>,
+}
+
+impl<R: RegisterAccess, const RX: usize, const TX: usize> BufferedUart<R, RX, TX> {
+	/// Number of bytes in the receive buffer above which the remote is asked to pause
+	const RX_HIGH: usize = RX * 3 / 4;
+	/// Number of bytes in the receive buffer below which the remote is asked to resume
+	const RX_LOW: usize = RX / 4;
+
+	/// Creates a new instance of `BufferedUart` using `uart`, which must be initialized.
+	pub const fn new(uart: Uart<R>) -> Self {
+		Self {
+			uart,
+			rx: RingBuffer::new(),
+			tx: RingBuffer::new(),
+			modem_events: RingBuffer::new(),
+			queue_modem_events: false,
+			xon_xoff: false,
+			rts_cts: false,
+			tx_paused: AtomicBool::new(false),
+			cts: AtomicBool::new(true),
+			rx_paused: AtomicBool::new(false),
+			control: AtomicBool::new(false),
+			rts_update: AtomicBool::new(false),
+			irq: None,
+		}
+	}
+
+	/// Returns the underlying UART peripheral
+	pub fn uart(&self) -> &Uart<R> {
+		&self.uart
+	}
+
+	/// Returns the underlying UART peripheral mutably, e.g. to change its settings or to
+	/// [`rebase`](Uart::rebase) it once the MMU maps it at another address.
+	pub fn uart_mut(&mut self) -> &mut Uart<R> {
+		&mut self.uart
+	}
+
+	/// Enables or disables XON/XOFF software flow control, disabled by default.
+	///
+	/// When enabled, the transmission is paused when XOFF is received and resumed when XON is
+	/// received, neither being stored in the receive buffer. XOFF is sent when the receive
+	/// buffer is three quarters full, and XON once it is drained to a quarter. When disabled
+	/// after XOFF was sent, XON is sent.
+	pub fn set_software_flow_control(&mut self, enabled: bool) {
+		// Also resumed if XON is pending, as it would be dropped
+		let resume = self.xon_xoff
+			&& !enabled
+			&& (self.rx_paused.load(Ordering::Relaxed) || self.control.load(Ordering::Relaxed));
+		self.xon_xoff = enabled;
+		self.tx_paused.store(false, Ordering::Relaxed);
+		self.control.store(false, Ordering::Relaxed);
+		if resume {
+			self.set_rx_paused(false);
+			self.send_control();
+		}
+	}
+
+	/// Enables or disables RTS/CTS hardware flow control managed by the driver, disabled by
+	/// default. This is meant for peripherals without auto flow control, with which
+	/// [`Uart::set_auto_flow_control`] should be preferred.
+	///
+	/// When enabled, request to send is deasserted when the receive buffer is three quarters
+	/// full and asserted again once it is drained to a quarter, and the transmission is paused
+	/// while clear to send is deasserted. Request to send is only written by the interrupt
+	/// handler once enabled, like the other bits of the modem control register. This requires
+	/// the modem status interrupt, which is enabled by [`enable`](Self::enable), or here if the
+	/// interrupts are already enabled. When disabled, request to send is asserted.
+	pub fn set_hardware_flow_control(&mut self, enabled: bool) {
+		let resume = self.rts_cts && !enabled && self.rx_paused.load(Ordering::Relaxed);
+		self.rts_cts = enabled;
+		self.cts
+			.store(self.uart.modem_status().cts, Ordering::Relaxed);
+		self.rts_update.store(false, Ordering::Relaxed);
+		if enabled {
+			self.uart.set_rts(!self.rx_paused.load(Ordering::Relaxed));
+			self.enable_modem_status_interrupt();
+		} else if resume {
+			self.uart.set_rts(true);
+			self.set_rx_paused(false);
+		}
+	}
+
+	/// Sets the interrupt controller called by [`enable`](Self::enable) and
+	/// [`handle_interrupt`](Self::handle_interrupt), none by default.
+	pub fn set_interrupt_controller(&mut self, irq: Option<&'static dyn InterruptController>) {
+		self.irq = irq;
+	}
+
+	/// Enables or disables the queue of modem status changes, disabled by default.
+	///
+	/// When enabled, the modem status read by the interrupt handler is queued each time clear
+	/// to send, data set ready or data carrier detect changed or the ring indicator went high,
+	/// and the changes are drained in order with [`modem_event`](Self::modem_event). Changes
+	/// are lost once the queue is full. This requires the modem status interrupt, which is
+	/// enabled by [`enable`](Self::enable), or here if the interrupts are already enabled.
+	pub fn set_modem_event_queue(&mut self, enabled: bool) {
+		self.queue_modem_events = enabled;
+		if enabled {
+			self.enable_modem_status_interrupt();
+		}
+	}
+
+	/// Enables the modem status interrupt if [`enable`](Self::enable) was called.
+	fn enable_modem_status_interrupt(&self) {
+		let ier = self.uart.ier();
+		if ier.received_data {
+			self.uart.set_ier(InterruptEnable {
+				modem_status: true,
+				..ier
+			});
+		}
+	}
+
+	/// Removes and returns the oldest queued modem status change, if any.
+	pub fn modem_event(&self) -> Option<ModemStatus> {
+		self.modem_events.pop().map(ModemStatus::from_bits)
+	}
+
+	/// Enables the received data available and receiver line status interrupts, and the modem
+	/// status interrupt if hardware flow control or the modem status change queue is enabled.
+	/// The interrupt line is then enabled like with [`Uart::set_ier`] if the peripheral is PC
+	/// compatible, and in the interrupt controller, if any.
+	pub fn enable(&self) {
+		let ier = self.uart.ier();
+		self.uart.set_ier(InterruptEnable {
+			received_data: true,
+			line_status: true,
+			modem_status: ier.modem_status || self.rts_cts || self.queue_modem_events,
+			..ier
+		});
+		if let Some(irq) = self.irq {
+			irq.enable();
+		}
+	}
+
+	/// Acknowledges the pending interrupts, moves the received data to the receive buffer and
+	/// the transmit buffer to the transmitter FIFO, and returns the collected events. Received
+	/// data is discarded if the receive buffer is full.
+	///
+	/// The receiver FIFO is drained entirely on both received data available and character
+	/// timeout interrupts, so that messages shorter than the trigger level are buffered as soon
+	/// as the line is idle for 4 character times rather than once more data arrives.
+	///
+	/// If an interrupt controller is set, the interrupt is claimed before and completed after.
+	pub fn handle_interrupt(&self) -> InterruptEvents {
+		let id = self.irq.map(|irq| irq.claim());
+		let events = self.handle_pending();
+		if let (Some(irq), Some(id)) = (self.irq, id) {
+			irq.complete(id);
+		}
+		events
+	}
+
+	/// Handles the pending interrupts until the receiver is left with no data.
+	fn handle_pending(&self) -> InterruptEvents {
+		let mut events = InterruptEvents::default();
+		loop {
+			let pending = self.uart.handle_interrupt();
+			events.merge(pending);
+			if let Some(msr) = pending.modem_status {
+				self.cts.store(msr.cts, Ordering::Relaxed);
+				if self.queue_modem_events && msr.has_changed() {
+					self.modem_events.push(msr.bits());
+				}
+				if self.rts_cts && msr.cts && self.tx_ready() {
+					self.set_tx_interrupt(true);
+				}
+			}
+			if pending.tx_empty {
+				self.refill();
+			}
+			if !pending.rx_ready {
+				return events;
+			}
+			while let Some(c) = self.uart.get() {
+				self.receive(c);
+			}
+		}
+	}
+
+	/// Moves `c` to the receive buffer, or handles it if it is a flow control character.
+	fn receive(&self, c: u8) {
+		if self.xon_xoff {
+			match c {
+				XOFF => return self.tx_paused.store(true, Ordering::Relaxed),
+				XON => {
+					self.tx_paused.store(false, Ordering::Relaxed);
+					return self.set_tx_interrupt(true);
+				}
+				_ => {}
+			}
+		}
+		if !self.rx.push(c) {
+			self.uart.stats.add(Counter::DroppedBytes, 1);
+		}
+		if self.rx.len() >= Self::RX_HIGH && !self.rx_paused.load(Ordering::Relaxed) {
+			self.set_rx_paused(true);
+		}
+	}
+
+	/// Asks the remote to pause or resume the transmission. Like the control characters,
+	/// request to send is updated by the interrupt handler, so that [`read`](Self::read) does
+	/// not write the modem control register concurrently with it.
+	fn set_rx_paused(&self, paused: bool) {
+		self.rx_paused.store(paused, Ordering::Relaxed);
+		if self.xon_xoff {
+			self.send_control();
+		}
+		if self.rts_cts {
+			self.rts_update.store(true, Ordering::Relaxed);
+			self.set_tx_interrupt(true);
+		}
+	}
+
+	/// Returns whether the transmission is paused by flow control.
+	fn tx_stopped(&self) -> bool {
+		self.tx_paused.load(Ordering::Relaxed)
+			|| (self.rts_cts && !self.cts.load(Ordering::Relaxed))
+	}
+
+	/// Sends XOFF if `rx_paused` is set, XON otherwise, before the transmit buffer and even if
+	/// the transmission is paused.
+	fn send_control(&self) {
+		self.control.store(true, Ordering::Relaxed);
+		self.set_tx_interrupt(true);
+	}
+
+	/// Returns whether there are bytes to send.
+	fn tx_ready(&self) -> bool {
+		self.control.load(Ordering::Relaxed) || (!self.tx_stopped() && self.tx.len() != 0)
+	}
+
+	/// Updates request to send if needed, and moves bytes from the transmit buffer to the empty
+	/// transmitter FIFO, disabling the transmitter holding register empty interrupt once there
+	/// are no bytes to send.
+	fn refill(&self) {
+		if self.rts_update.load(Ordering::Relaxed) {
+			self.rts_update.store(false, Ordering::Relaxed);
+			// Loaded after clearing `rts_update` so that a concurrent change is applied next time
+			self.uart.set_rts(!self.rx_paused.load(Ordering::Relaxed));
+		}
+		let mut burst = self.uart.fifo_depth();
+		if self.control.load(Ordering::Relaxed) {
+			self.control.store(false, Ordering::Relaxed);
+			// Loaded after clearing `control` so that a concurrent change is sent next time
+			let c = if self.rx_paused.load(Ordering::Relaxed) {
+				XOFF
+			} else {
+				XON
+			};
+			self.uart.write_thr(c);
+			burst -= 1;
+		}
+		for _ in 0..burst {
+			let c = if self.tx_stopped() {
+				None
+			} else {
+				self.tx.pop()
+			};
+			match c {
+				Some(c) => self.uart.write_thr(c),
+				None => {
+					self.set_tx_interrupt(false);
+					// A byte may have been queued before the interrupt was disabled
+					if self.tx_ready() {
+						self.set_tx_interrupt(true);
+					}
+					return;
+				}
+			}
+		}
+	}
+
+	fn set_tx_interrupt(&self, thr_empty: bool) {
+		self.uart.set_ier(InterruptEnable {
+			thr_empty,
+			..self.uart.ier()
+		});
+	}
+
+	/// Moves the buffered received data to `buf` without blocking, and returns the number of
+	/// bytes read.
+	pub fn read(&self, buf: &mut [u8]) -> usize {
+		let read = buf
+			.iter_mut()
+			.map_while(|b| self.rx.pop().map(|c| *b = c))
+			.count();
+		if self.rx_paused.load(Ordering::Relaxed) && self.rx.len() <= Self::RX_LOW {
+			self.set_rx_paused(false);
+		}
+		read
+	}
+
+	/// Returns the number of bytes in the receive buffer.
+	pub fn bytes_available(&self) -> usize {
+		self.rx.len()
+	}
+
+	/// Moves bytes from `buf` to the transmit buffer without blocking, enabling the transmitter
+	/// holding register empty interrupt, and returns the number of bytes written.
+	pub fn write(&self, buf: &[u8]) -> usize {
+		let written = buf.iter().take_while(|&&c| self.tx.push(c)).count();
+		if written != 0 {
+			self.set_tx_interrupt(true);
+		}
+		written
+	}
+
+	/// Moves `bytes` to the transmit buffer, waiting for the interrupt handler to free space in
+	/// it if necessary.
+	fn write_blocking(&self, mut bytes: &[u8]) {
+		while !bytes.is_empty() {
+			self.uart.wait_until(|| !self.tx.is_full());
+			bytes = &bytes[self.write(bytes)..];
+		}
+	}
+
+	/// Returns the number of bytes in the transmit buffer.
+	pub fn bytes_pending(&self) -> usize {
+		self.tx.len()
+	}
+}
+
+impl<R: RegisterAccess, const RX: usize, const TX: usize> Write for BufferedUart<R, RX, TX> {
+	/// Moves `s` to the transmit buffer, applying the newline translation, waiting for the
+	/// interrupt handler to free space in it if necessary. The interrupt handler must therefore
+	/// be able to run.
+	fn write_str(&mut self, s: &str) -> Result {
+		if !self.uart.newline.lf_to_crlf {
+			self.write_blocking(s.as_bytes());
+			return Ok(());
+		}
+		for (i, line) in s.split('\n').enumerate() {
+			if i != 0 {
+				self.write_blocking(b"\r\n");
+			}
+			self.write_blocking(line.as_bytes());
+		}
+		Ok(())
+	}
+}
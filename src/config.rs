@@ -0,0 +1,143 @@
+//! Line configuration.
+
+use crate::{baud_divisor, BaudRate, Break, DMAMode, Divisor, Parity, StopBits, WordLength};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Configuration of the UART peripheral, used by [`Uart::init_with`](crate::Uart::init_with)
+///
+/// The default configuration is 8 data bits, no parity, 1 stop bit, at 115200 bauds, with the
+/// FIFOs enabled.
+pub struct Config {
+	/// Word length
+	pub word_length: WordLength,
+	/// Number of stop bits
+	pub stop_bits: StopBits,
+	/// Parity
+	pub parity: Parity,
+	/// Break
+	pub break_: Break,
+	/// DMA mode select
+	pub dma_mode: DMAMode,
+	/// Whether the FIFOs are enabled. When disabled, the peripheral operates like a 16450, one
+	/// byte at a time.
+	pub fifo: bool,
+	/// Divisor latch value for setting the baud rate
+	pub divisor: u16,
+}
+
+impl Config {
+	/// Creates a new instance of `Config` with the default configuration.
+	pub const fn new() -> Self {
+		Self {
+			word_length: WordLength::EIGHT,
+			stop_bits: StopBits::ONE,
+			parity: Parity::NONE,
+			break_: Break::DISABLE,
+			dma_mode: DMAMode::MODE0,
+			fifo: true,
+			divisor: Divisor::BAUD115200 as u16,
+		}
+	}
+
+	/// Sets the word length.
+	pub const fn word_length(mut self, word_length: WordLength) -> Self {
+		self.word_length = word_length;
+		self
+	}
+
+	/// Sets the number of stop bits.
+	pub const fn stop_bits(mut self, stop_bits: StopBits) -> Self {
+		self.stop_bits = stop_bits;
+		self
+	}
+
+	/// Sets the parity.
+	pub const fn parity(mut self, parity: Parity) -> Self {
+		self.parity = parity;
+		self
+	}
+
+	/// Sets the break.
+	pub const fn break_(mut self, break_: Break) -> Self {
+		self.break_ = break_;
+		self
+	}
+
+	/// Sets the DMA mode.
+	pub const fn dma_mode(mut self, dma_mode: DMAMode) -> Self {
+		self.dma_mode = dma_mode;
+		self
+	}
+
+	/// Enables or disables the FIFOs.
+	pub const fn fifo(mut self, fifo: bool) -> Self {
+		self.fifo = fifo;
+		self
+	}
+
+	/// Sets the divisor for setting the baud rate.
+	pub const fn baud(mut self, divisor: Divisor) -> Self {
+		self.divisor = divisor as u16;
+		self
+	}
+
+	/// Sets the divisor generating `baud_rate` from an input clock of `clock_hz` Hz.
+	pub const fn baud_rate(mut self, baud_rate: BaudRate, clock_hz: u32) -> Self {
+		self.divisor = baud_rate.divisor(clock_hz);
+		self
+	}
+
+	/// Sets the raw divisor latch value for setting the baud rate, e.g. as computed by
+	/// [`baud_divisor`](crate::baud_divisor).
+	pub const fn divisor(mut self, divisor: u16) -> Self {
+		self.divisor = divisor;
+		self
+	}
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Properties of a `ns16550a` or `snps,dw-apb-uart` device tree node, used by
+/// [`Uart::from_dt_properties`](crate::Uart::from_dt_properties)
+pub struct DtProperties {
+	/// Address of the registers, from the `reg` property
+	pub base_address: usize,
+	/// `reg-shift` property, 0 if absent
+	pub reg_shift: u32,
+	/// `reg-io-width` property, 1 if absent
+	pub reg_io_width: u32,
+	/// `clock-frequency` property, in Hz
+	pub clock_frequency: u32,
+	/// `current-speed` property, in bauds, 0 if absent
+	pub current_speed: u32,
+}
+
+impl DtProperties {
+	/// Creates a new instance of `DtProperties` with the given base address and clock frequency,
+	/// and the optional properties absent.
+	pub const fn new(base_address: usize, clock_frequency: u32) -> Self {
+		Self {
+			base_address,
+			reg_shift: 0,
+			reg_io_width: 1,
+			clock_frequency,
+			current_speed: 0,
+		}
+	}
+
+	/// Returns the default configuration, at `current_speed` bauds or 115200 bauds if it is
+	/// absent, with the divisor computed from `clock_frequency`.
+	pub const fn config(&self) -> Config {
+		let baud = if self.current_speed == 0 {
+			115200
+		} else {
+			self.current_speed
+		};
+		Config::new().divisor(baud_divisor(self.clock_frequency, baud))
+	}
+}
@@ -0,0 +1,159 @@
+//! Global console used by the [`print!`](crate::print) and [`println!`](crate::println)
+//! macros.
+
+use crate::{RegisterAccess, Uart};
+use core::cell::UnsafeCell;
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Output of the console
+trait Output: Sync {
+	fn write_str(&self, s: &str) -> fmt::Result;
+}
+
+impl<R: RegisterAccess + Sync> Output for Uart<R> {
+	fn write_str(&self, s: &str) -> fmt::Result {
+		self.write_text(s)
+	}
+}
+
+/// Adapter implementing `Write` on top of an `Output`
+struct Writer<'a>(&'a dyn Output);
+
+impl Write for Writer<'_> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.0.write_str(s)
+	}
+}
+
+/// Console protected by a spin lock
+struct Console {
+	locked: AtomicBool,
+	output: UnsafeCell<Option<&'static dyn Output>>,
+}
+
+// The output is only accessed while holding the lock.
+unsafe impl Sync for Console {}
+
+impl Console {
+	fn lock(&self) {
+		while self
+			.locked
+			.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+			.is_err()
+		{
+			core::hint::spin_loop();
+		}
+	}
+
+	/// Acquires the lock if it is free, returning whether it was acquired.
+	fn try_lock(&self) -> bool {
+		self.locked
+			.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+			.is_ok()
+	}
+
+	fn unlock(&self) {
+		self.locked.store(false, Ordering::Release);
+	}
+}
+
+static CONSOLE: Console = Console {
+	locked: AtomicBool::new(false),
+	output: UnsafeCell::new(None),
+};
+
+/// Registers `uart` as the console used by the [`print!`](crate::print) and
+/// [`println!`](crate::println) macros. Output written before a console is registered is
+/// discarded.
+pub fn set_console<R: RegisterAccess + Sync>(uart: &'static Uart<R>) {
+	CONSOLE.lock();
+	unsafe {
+		*CONSOLE.output.get() = Some(uart);
+	}
+	CONSOLE.unlock();
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+	CONSOLE.lock();
+	if let Some(output) = unsafe { *CONSOLE.output.get() } {
+		let _ = Writer(output).write_fmt(args);
+	}
+	CONSOLE.unlock();
+}
+
+#[doc(hidden)]
+pub fn _try_print(args: fmt::Arguments) -> bool {
+	if !CONSOLE.try_lock() {
+		return false;
+	}
+	if let Some(output) = unsafe { *CONSOLE.output.get() } {
+		let _ = Writer(output).write_fmt(args);
+	}
+	CONSOLE.unlock();
+	true
+}
+
+/// Prints the panic message and location to the console registered with
+/// [`set_console`](crate::set_console), to be called from the panic handler:
+///
+/// ``` ignore
+/// #[panic_handler]
+/// fn panic(info: &core::panic::PanicInfo) -> ! {
+///     ns16550a::print_panic(info);
+///     loop {}
+/// }
+/// ```
+///
+/// The console lock is ignored, so that the message is printed even if the panic occurred
+/// while printing.
+pub fn print_panic(info: &PanicInfo) {
+	if let Some(output) = unsafe { *CONSOLE.output.get() } {
+		let _ = writeln!(Writer(output), "{}", info);
+	}
+}
+
+/// Prints to the console registered with [`set_console`](crate::set_console).
+#[macro_export]
+macro_rules! print {
+	($($arg:tt)*) => {
+		$crate::_print(format_args!($($arg)*))
+	};
+}
+
+/// Prints to the console registered with [`set_console`](crate::set_console), with a
+/// newline.
+#[macro_export]
+macro_rules! println {
+	() => {
+		$crate::print!("\n")
+	};
+	($($arg:tt)*) => {
+		$crate::print!("{}\n", format_args!($($arg)*))
+	};
+}
+
+/// Prints to the console registered with [`set_console`](crate::set_console) if its lock is
+/// free, and evaluates to whether it was. Unlike [`print!`](crate::print), this never waits
+/// for the lock, so it can be used from an interrupt handler which may have interrupted a
+/// print.
+#[macro_export]
+macro_rules! try_print {
+	($($arg:tt)*) => {
+		$crate::_try_print(format_args!($($arg)*))
+	};
+}
+
+/// Prints to the console registered with [`set_console`](crate::set_console), with a newline,
+/// if its lock is free, and evaluates to whether it was. See [`try_print!`](crate::try_print).
+#[macro_export]
+macro_rules! try_println {
+	() => {
+		$crate::try_print!("\n")
+	};
+	($($arg:tt)*) => {
+		$crate::try_print!("{}\n", format_args!($($arg)*))
+	};
+}
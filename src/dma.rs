@@ -0,0 +1,65 @@
+//! Integration with an external DMA controller.
+
+use crate::{DMAMode, FifoControl, IoWidth, Register, RegisterAccess, Uart};
+use core::sync::atomic::Ordering;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Parameters for programming an external DMA controller to move data to or from the
+/// peripheral, returned by [`Uart::dma_target`]
+pub struct DmaTarget {
+	/// Address of the receiver buffer and transmitter holding registers
+	pub data_address: usize,
+	/// Width of the accesses to the data registers, of which only the low byte is significant
+	pub width: IoWidth,
+	/// Number of characters in the receiver FIFO when RXRDY becomes active in DMA mode 1
+	/// because of the trigger level. On a character timeout, fewer characters are available,
+	/// so the DMA controller must stop at the end of the burst when RXRDY becomes inactive.
+	pub rx_burst: usize,
+	/// Number of characters that can be written when TXRDY becomes active in DMA mode 1
+	pub tx_burst: usize,
+}
+
+impl Uart {
+	/// Returns the parameters for programming an external DMA controller, according to the
+	/// capabilities and the FIFO configuration last set through this handle.
+	pub fn dma_target(&self) -> DmaTarget {
+		let fcr = FifoControl::from_bits(self.fcr.load(Ordering::Relaxed));
+		let rx_burst = if !fcr.enable {
+			1
+		} else if self.capabilities.fifo_depth != 0 && !fcr.fifo64 {
+			fcr.trigger_level
+				.bytes_with_depth(self.capabilities.fifo_depth)
+		} else {
+			fcr.trigger_level.bytes(fcr.fifo64)
+		};
+		DmaTarget {
+			data_address: self.regs.register_address(Register::RBR),
+			width: self.regs.reg_io_width(),
+			rx_burst,
+			tx_burst: self.fifo_depth(),
+		}
+	}
+}
+
+impl<R: RegisterAccess> Uart<R> {
+	/// Starts DMA-paced transfers, by selecting the DMA mode 1 and keeping the rest of the
+	/// FIFO configuration last set through this handle. The DMA controller must be programmed
+	/// beforehand, e.g. with [`dma_target`](Uart::dma_target), and the received data available
+	/// and transmitter holding register empty interrupts should be disabled.
+	pub fn start_dma(&self) {
+		self.set_dma_mode(DMAMode::MODE1);
+	}
+
+	/// Stops DMA-paced transfers, by selecting the DMA mode 0 and keeping the rest of the FIFO
+	/// configuration last set through this handle.
+	pub fn stop_dma(&self) {
+		self.set_dma_mode(DMAMode::MODE0);
+	}
+
+	fn set_dma_mode(&self, dma_mode: DMAMode) {
+		self.set_fifo_control(FifoControl {
+			dma_mode,
+			..FifoControl::from_bits(self.fcr.load(Ordering::Relaxed))
+		});
+	}
+}
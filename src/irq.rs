@@ -0,0 +1,20 @@
+//! Interrupt controller glue.
+//!
+//! [`BufferedUart`](crate::BufferedUart) and [`AsyncUart`](crate::AsyncUart) can be given an
+//! [`InterruptController`], e.g. a PLIC, GIC or PIC driver, which they call when enabling the
+//! interrupts of the peripheral and around the handling of each interrupt, so that the
+//! interrupt handler only has to call their `handle_interrupt`.
+
+/// Interrupt controller the interrupt line of the peripheral is connected to
+pub trait InterruptController: Sync {
+	/// Enables the interrupt line of the peripheral, called when the interrupts of the
+	/// peripheral are enabled
+	fn enable(&self);
+
+	/// Claims the interrupt before the peripheral is handled, and returns its identifier
+	fn claim(&self) -> u32;
+
+	/// Signals the completion of the interrupt `id` returned by [`claim`](Self::claim), once the
+	/// peripheral is handled
+	fn complete(&self, id: u32);
+}
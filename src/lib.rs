@@ -2,13 +2,216 @@
 
 #![no_std]
 
+#[cfg(target_has_atomic = "8")]
+mod asynch;
+mod at;
+mod buf_writer;
+mod buffered;
+mod config;
+#[cfg(all(feature = "console", target_has_atomic = "8"))]
+mod console;
+mod dma;
+mod irq;
+mod lossy;
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+#[cfg(feature = "omap")]
+mod omap;
+mod oxford;
+#[cfg(all(feature = "port-io", any(target_arch = "x86", target_arch = "x86_64")))]
+mod port;
+mod register;
+mod ring;
+mod split;
+mod stats;
+#[cfg(any(test, feature = "mock"))]
+mod trace;
+mod wait;
+
+#[cfg(target_has_atomic = "8")]
+pub use asynch::AsyncUart;
+pub use at::UartAt;
+pub use buf_writer::BufWriter;
+pub use buffered::BufferedUart;
+pub use config::{Config, DtProperties};
+#[cfg(all(feature = "console", target_has_atomic = "8"))]
+#[doc(hidden)]
+pub use console::{_print, _try_print};
+#[cfg(all(feature = "console", target_has_atomic = "8"))]
+pub use console::{print_panic, set_console};
+pub use dma::DmaTarget;
+pub use irq::InterruptController;
+pub use lossy::LossyWriter;
+
+#[cfg(any(test, feature = "mock"))]
+pub use mock::MockRegisters;
+#[cfg(feature = "omap")]
+pub use omap::OmapMode;
+#[cfg(all(feature = "port-io", any(target_arch = "x86", target_arch = "x86_64")))]
+pub use port::{PortIo, SerialPorts};
+pub use register::{IoWidth, Mmio, MmioAt, Register, RegisterAccess};
+pub use split::{UartRx, UartTx};
+#[cfg(all(feature = "stats", target_has_atomic = "ptr"))]
+pub use stats::Stats;
+#[cfg(any(test, feature = "mock"))]
+pub use trace::{Access, ReplayError, TraceRecorder, TraceReplayer};
+#[cfg(any(
+	target_arch = "riscv32",
+	target_arch = "riscv64",
+	target_arch = "arm",
+	target_arch = "aarch64"
+))]
+pub use wait::Wfi;
+pub use wait::{Spin, WaitStrategy};
+
 use core::fmt::{Result, Write};
+#[cfg(target_has_atomic = "ptr")]
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::{AtomicU8, Ordering};
+use stats::{Counter, Counters};
+
+/// Struct representing a NS16550A UART peripheral, whose registers are accessed through `R`
+pub struct Uart<R = Mmio> {
+	/// Access to the registers of the peripheral
+	regs: R,
+	/// Last value written to the FIFO control register, which is write-only
+	fcr: AtomicU8,
+	/// Error bits of the line status register read since the receiver buffer register was last
+	/// read, as reading the line status register clears them
+	lsr_errors: AtomicU8,
+	/// Last value written to the additional control register of the 16C950, which is
+	/// write-only
+	acr: AtomicU8,
+	/// Features supported by the peripheral beyond the NS16550A
+	capabilities: Capabilities,
+	/// Strategy called by the blocking operations while the peripheral is not ready
+	wait: &'static dyn WaitStrategy,
+	/// RS-485 half-duplex transmission, if enabled
+	rs485: Option<Rs485>,
+	/// Newline translation
+	newline: Newline,
+	/// Statistics, only collected with the `stats` feature
+	stats: Counters,
+	/// Hook called by [`Uart::handle_interrupt`] when errors are reported
+	error_hook: Option<&'static (dyn Fn(InterruptEvents) + Sync)>,
+	/// Whether the interrupt line is gated by the user output 2
+	pc_compatible: bool,
+	/// Hook called by [`Uart::write_bytes`] between the bytes
+	write_hook: Option<&'static (dyn Fn(usize) -> bool + Sync)>,
+}
+
+impl<R: core::fmt::Debug> core::fmt::Debug for Uart<R> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result {
+		f.debug_struct("Uart")
+			.field("regs", &self.regs)
+			.field("fcr", &self.fcr)
+			.field("lsr_errors", &self.lsr_errors)
+			.field("acr", &self.acr)
+			.field("capabilities", &self.capabilities)
+			.field("rs485", &self.rs485)
+			.field("newline", &self.newline)
+			.field("pc_compatible", &self.pc_compatible)
+			.finish_non_exhaustive()
+	}
+}
 
-#[derive(Copy, Clone, Debug)]
-/// Struct representing a NS16550A UART peripheral
-pub struct Uart {
-	/// Base address of the peripheral
-	base_address: usize,
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Newline translation
+pub struct Newline {
+	/// Translates `\n` into `\r\n` when writing strings through the [`Write`] implementations
+	pub lf_to_crlf: bool,
+	/// Translates the received `\r` into `\n`
+	pub cr_to_lf: bool,
+}
+
+impl Newline {
+	/// No translation
+	pub const NONE: Self = Self {
+		lf_to_crlf: false,
+		cr_to_lf: false,
+	};
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// RS-485 half-duplex transmission, with the request to send output enabling the transmitter
+/// of the transceiver
+pub struct Rs485 {
+	/// Whether request to send is set while transmitting, and cleared otherwise, or the
+	/// opposite
+	pub rts_on_send: bool,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Features supported by the peripheral beyond the NS16550A
+///
+/// The bits controlling a feature are never set if the corresponding capability is not
+/// enabled.
+pub struct Capabilities {
+	/// 64 bytes FIFO (16750)
+	pub fifo64: bool,
+	/// Automatic RTS/CTS flow control (16750)
+	pub auto_flow_control: bool,
+	/// Sleep mode (16750)
+	pub sleep_mode: bool,
+	/// Width in bits of the fractional divisor latch (DesignWare), 0 if there is none
+	pub fractional_divisor: u8,
+	/// Depth in bytes of the FIFOs (DesignWare, 16C950 enhanced mode, or as measured by
+	/// [`Uart::measure_fifo_depth`]), 0 for the standard 16 bytes FIFO
+	pub fifo_depth: usize,
+	/// 128 bytes FIFO, enhanced mode and flexible trigger levels (16C950)
+	pub fifo128: bool,
+	/// Clock prescaler dividing the input clock by 4, selected by bit 7 of the modem control
+	/// register (16750 variants, 16C950 in enhanced mode)
+	pub prescaler: bool,
+}
+
+impl Capabilities {
+	/// Capabilities of the NS16550A
+	pub const NONE: Self = Self {
+		fifo64: false,
+		auto_flow_control: false,
+		sleep_mode: false,
+		fractional_divisor: 0,
+		fifo_depth: 0,
+		fifo128: false,
+		prescaler: false,
+	};
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// UART model, as identified by [`Uart::probe`]
+pub enum Model {
+	/// No FIFO and no scratch register
+	NS8250,
+	/// No FIFO
+	NS16450,
+	/// 16 bytes FIFO, reported as unusable
+	NS16550,
+	/// 16 bytes FIFO
+	NS16550A,
+	/// 64 bytes FIFO, automatic flow control and sleep mode
+	TL16C750,
+	/// 128 bytes FIFO and enhanced mode
+	OX16C950,
+}
+
+impl Model {
+	/// Returns the capabilities of the model.
+	pub const fn capabilities(self) -> Capabilities {
+		match self {
+			Self::TL16C750 => Capabilities {
+				fifo64: true,
+				auto_flow_control: true,
+				sleep_mode: true,
+				..Capabilities::NONE
+			},
+			Self::OX16C950 => Capabilities {
+				fifo128: true,
+				..Capabilities::NONE
+			},
+			_ => Capabilities::NONE,
+		}
+	}
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -27,6 +230,45 @@ pub enum StopBits {
 	TWO = 1,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Parity
+pub enum Parity {
+	/// No parity bit
+	NONE = 0x00,
+	/// Odd number of logic 1s in the data and parity bits
+	ODD = 0x08,
+	/// Even number of logic 1s in the data and parity bits
+	EVEN = 0x18,
+	/// Parity bit always 1
+	MARK = 0x28,
+	/// Parity bit always 0
+	SPACE = 0x38,
+}
+
+impl Parity {
+	fn from_bits(bits: u8) -> Self {
+		match bits & 0x38 {
+			0x08 => Self::ODD,
+			0x18 => Self::EVEN,
+			0x28 => Self::MARK,
+			0x38 => Self::SPACE,
+			_ => Self::NONE,
+		}
+	}
+
+	#[allow(deprecated)]
+	fn from_legacy(
+		parity_bit: ParityBit,
+		parity_select: ParitySelect,
+		stick_parity: StickParity,
+	) -> Self {
+		Self::from_bits(
+			((parity_bit as u8) << 3) | ((parity_select as u8) << 4) | ((stick_parity as u8) << 5),
+		)
+	}
+}
+
+#[deprecated(since = "0.5.0", note = "use `Parity` instead")]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 /// Parity bits
 pub enum ParityBit {
@@ -34,6 +276,7 @@ pub enum ParityBit {
 	ENABLE = 1,
 }
 
+#[deprecated(since = "0.5.0", note = "use `Parity` instead")]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 /// Parity select
 pub enum ParitySelect {
@@ -41,6 +284,7 @@ pub enum ParitySelect {
 	ODD = 1,
 }
 
+#[deprecated(since = "0.5.0", note = "use `Parity` instead")]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 /// Stick parity
 pub enum StickParity {
@@ -63,15 +307,492 @@ pub enum DLAB {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-/// DMA mode select
+/// DMA mode select, controlling the RXRDY and TXRDY outputs requesting DMA transfers
 pub enum DMAMode {
+	/// Single transfers: RXRDY is active while a character is received, and TXRDY while the
+	/// transmitter holding register or FIFO is empty
 	MODE0 = 0,
+	/// Multiple transfers, with the FIFOs enabled: RXRDY is active from the trigger level or a
+	/// character timeout until the receiver FIFO is empty, and TXRDY from the transmitter FIFO
+	/// being empty until it is full
 	MODE1 = 1,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Receiver FIFO trigger level
+///
+/// The names correspond to the 16 bytes FIFO. When the 64 bytes FIFO of the 16750 is enabled,
+/// the trigger levels are 1, 16, 32 and 56 bytes.
+pub enum TriggerLevel {
+	ONE = 0,
+	FOUR = 1,
+	EIGHT = 2,
+	FOURTEEN = 3,
+}
+
+impl TriggerLevel {
+	/// Returns the number of bytes corresponding to the trigger level, depending on whether the
+	/// 64 bytes FIFO is enabled.
+	pub const fn bytes(self, fifo64: bool) -> usize {
+		match (self, fifo64) {
+			(Self::ONE, _) => 1,
+			(Self::FOUR, false) => 4,
+			(Self::EIGHT, false) => 8,
+			(Self::FOURTEEN, false) => 14,
+			(Self::FOUR, true) => 16,
+			(Self::EIGHT, true) => 32,
+			(Self::FOURTEEN, true) => 56,
+		}
+	}
+
+	/// Returns the number of bytes corresponding to the trigger level of a DesignWare peripheral
+	/// with FIFOs of `depth` bytes, whose trigger levels are 1 byte, a quarter, half and 2 bytes
+	/// less than the FIFO depth.
+	pub const fn bytes_with_depth(self, depth: usize) -> usize {
+		match self {
+			Self::ONE => 1,
+			Self::FOUR => depth / 4,
+			Self::EIGHT => depth / 2,
+			Self::FOURTEEN => depth - 2,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// FIFO control register
+pub struct FifoControl {
+	/// Enables the transmitter and receiver FIFOs
+	pub enable: bool,
+	/// Clears the receiver FIFO and resets its counter logic
+	pub reset_rx: bool,
+	/// Clears the transmitter FIFO and resets its counter logic
+	pub reset_tx: bool,
+	/// DMA mode select
+	pub dma_mode: DMAMode,
+	/// Receiver FIFO trigger level
+	pub trigger_level: TriggerLevel,
+	/// Enables the 64 bytes FIFO of the 16750. Requires [`Capabilities::fifo64`].
+	pub fifo64: bool,
+}
+
+impl Default for FifoControl {
+	fn default() -> Self {
+		Self {
+			enable: true,
+			reset_rx: false,
+			reset_tx: false,
+			dma_mode: DMAMode::MODE0,
+			trigger_level: TriggerLevel::ONE,
+			fifo64: false,
+		}
+	}
+}
+
+impl FifoControl {
+	fn from_bits(bits: u8) -> Self {
+		Self {
+			enable: bits & 0x01 != 0,
+			reset_rx: bits & 0x02 != 0,
+			reset_tx: bits & 0x04 != 0,
+			dma_mode: if bits & 0x08 == 0 {
+				DMAMode::MODE0
+			} else {
+				DMAMode::MODE1
+			},
+			trigger_level: match bits >> 6 {
+				0 => TriggerLevel::ONE,
+				1 => TriggerLevel::FOUR,
+				2 => TriggerLevel::EIGHT,
+				_ => TriggerLevel::FOURTEEN,
+			},
+			fifo64: bits & 0x20 != 0,
+		}
+	}
+
+	fn bits(&self) -> u8 {
+		self.enable as u8
+			| ((self.reset_rx as u8) << 1)
+			| ((self.reset_tx as u8) << 2)
+			| ((self.dma_mode as u8) << 3)
+			| ((self.fifo64 as u8) << 5)
+			| ((self.trigger_level as u8) << 6)
+	}
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Interrupt enable register
+pub struct InterruptEnable {
+	/// Received data available interrupt
+	pub received_data: bool,
+	/// Transmitter holding register empty interrupt
+	pub thr_empty: bool,
+	/// Receiver line status interrupt
+	pub line_status: bool,
+	/// Modem status interrupt
+	pub modem_status: bool,
+	/// Sleep mode. Requires [`Capabilities::sleep_mode`].
+	pub sleep_mode: bool,
+}
+
+impl InterruptEnable {
+	fn from_bits(bits: u8) -> Self {
+		Self {
+			received_data: bits & 0x01 != 0,
+			thr_empty: bits & 0x02 != 0,
+			line_status: bits & 0x04 != 0,
+			modem_status: bits & 0x08 != 0,
+			sleep_mode: bits & 0x10 != 0,
+		}
+	}
+
+	fn bits(&self) -> u8 {
+		self.received_data as u8
+			| ((self.thr_empty as u8) << 1)
+			| ((self.line_status as u8) << 2)
+			| ((self.modem_status as u8) << 3)
+			| ((self.sleep_mode as u8) << 4)
+	}
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Modem control register
+pub struct ModemControl {
+	/// Data terminal ready
+	pub dtr: bool,
+	/// Request to send
+	pub rts: bool,
+	/// User output 1
+	pub out1: bool,
+	/// User output 2
+	pub out2: bool,
+	/// Loopback mode
+	pub loopback: bool,
+	/// Automatic flow control. Automatic CTS is enabled, and automatic RTS as well if `rts` is
+	/// set. Requires [`Capabilities::auto_flow_control`].
+	pub auto_flow_control: bool,
+	/// Divides the input clock by 4. Requires [`Capabilities::prescaler`].
+	pub prescaler: bool,
+}
+
+impl ModemControl {
+	fn from_bits(bits: u8) -> Self {
+		Self {
+			dtr: bits & 0x01 != 0,
+			rts: bits & 0x02 != 0,
+			out1: bits & 0x04 != 0,
+			out2: bits & 0x08 != 0,
+			loopback: bits & 0x10 != 0,
+			auto_flow_control: bits & 0x20 != 0,
+			prescaler: bits & 0x80 != 0,
+		}
+	}
+
+	fn bits(&self) -> u8 {
+		self.dtr as u8
+			| ((self.rts as u8) << 1)
+			| ((self.out1 as u8) << 2)
+			| ((self.out2 as u8) << 3)
+			| ((self.loopback as u8) << 4)
+			| ((self.auto_flow_control as u8) << 5)
+			| ((self.prescaler as u8) << 7)
+	}
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Line status register
+pub struct LineStatus {
+	/// A character was received and is in the receiver buffer register or FIFO
+	pub data_ready: bool,
+	/// A character was lost because the receiver buffer was full
+	pub overrun_error: bool,
+	/// The received character has a wrong parity
+	pub parity_error: bool,
+	/// The received character does not have a valid stop bit
+	pub framing_error: bool,
+	/// A break condition was detected on the receive line
+	pub break_interrupt: bool,
+	/// The transmitter holding register or FIFO is empty
+	pub thr_empty: bool,
+	/// Both the transmitter holding register and the transmitter shift register are empty
+	pub transmitter_empty: bool,
+	/// At least one parity error, framing error or break indication is in the receiver FIFO
+	pub fifo_error: bool,
+}
+
+impl LineStatus {
+	fn from_bits(bits: u8) -> Self {
+		Self {
+			data_ready: bits & 0x01 != 0,
+			overrun_error: bits & 0x02 != 0,
+			parity_error: bits & 0x04 != 0,
+			framing_error: bits & 0x08 != 0,
+			break_interrupt: bits & 0x10 != 0,
+			thr_empty: bits & 0x20 != 0,
+			transmitter_empty: bits & 0x40 != 0,
+			fifo_error: bits & 0x80 != 0,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Receive error
+pub enum RxError {
+	/// A character was lost because the receiver buffer was full
+	Overrun,
+	/// The received character has a wrong parity
+	Parity,
+	/// The received character does not have a valid stop bit
+	Framing,
+	/// A break condition was detected on the receive line
+	Break,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Character of the 9-bit multidrop protocol, whose 9th bit is the parity bit
+pub enum MultidropByte {
+	/// Address character, with the 9th bit set
+	Address(u8),
+	/// Data character, with the 9th bit cleared
+	Data(u8),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Error returned when a blocking operation did not complete in time
+pub struct TimedOut;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Error returned by [`Uart::try_new`] when the base address is invalid
+pub enum AddressError {
+	/// The base address is 0
+	Null,
+	/// The base address or the register spacing is not aligned to the width of the register
+	/// accesses
+	Misaligned,
+	/// The register shift is not less than the number of bits of an address
+	Shift,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Error returned when the divisor latch does not hold the value written, e.g. because the
+/// peripheral ignored the writes while busy
+pub struct DivisorMismatch {
+	/// Divisor written
+	pub written: u16,
+	/// Divisor read back
+	pub read: u16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Snapshot of the registers, returned by [`Uart::dump_registers`]
+pub struct RegisterDump {
+	/// Interrupt enable register
+	pub ier: u8,
+	/// Interrupt identification register
+	pub iir: u8,
+	/// Line control register
+	pub lcr: u8,
+	/// Modem control register
+	pub mcr: u8,
+	/// Line status register
+	pub lsr: u8,
+	/// Modem status register
+	pub msr: u8,
+	/// Scratch register
+	pub scr: u8,
+	/// Divisor latch
+	pub divisor: u16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Self-test error
+pub enum SelfTestError {
+	/// The transmitter did not accept or the receiver did not return a character in time
+	Timeout,
+	/// The character received differs from the one sent
+	Mismatch {
+		/// Character sent
+		expected: u8,
+		/// Character received
+		received: u8,
+	},
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Modem status register
+pub struct ModemStatus {
+	/// Clear to send changed since the last read
+	pub delta_cts: bool,
+	/// Data set ready changed since the last read
+	pub delta_dsr: bool,
+	/// Ring indicator changed from low to high since the last read
+	pub trailing_edge_ri: bool,
+	/// Data carrier detect changed since the last read
+	pub delta_dcd: bool,
+	/// Clear to send
+	pub cts: bool,
+	/// Data set ready
+	pub dsr: bool,
+	/// Ring indicator
+	pub ri: bool,
+	/// Data carrier detect
+	pub dcd: bool,
+}
+
+impl ModemStatus {
+	fn from_bits(bits: u8) -> Self {
+		Self {
+			delta_cts: bits & 0x01 != 0,
+			delta_dsr: bits & 0x02 != 0,
+			trailing_edge_ri: bits & 0x04 != 0,
+			delta_dcd: bits & 0x08 != 0,
+			cts: bits & 0x10 != 0,
+			dsr: bits & 0x20 != 0,
+			ri: bits & 0x40 != 0,
+			dcd: bits & 0x80 != 0,
+		}
+	}
+
+	fn bits(self) -> u8 {
+		self.delta_cts as u8
+			| (self.delta_dsr as u8) << 1
+			| (self.trailing_edge_ri as u8) << 2
+			| (self.delta_dcd as u8) << 3
+			| (self.cts as u8) << 4
+			| (self.dsr as u8) << 5
+			| (self.ri as u8) << 6
+			| (self.dcd as u8) << 7
+	}
+
+	/// Returns whether a line changed since the last read.
+	pub fn has_changed(&self) -> bool {
+		self.delta_cts || self.delta_dsr || self.trailing_edge_ri || self.delta_dcd
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Cause of a pending interrupt, decoded from the interrupt identification register
+pub enum InterruptCause {
+	/// Overrun, parity or framing error, or break interrupt
+	LineStatus,
+	/// Received data available or trigger level reached
+	ReceivedDataAvailable,
+	/// No character removed from or input to the receiver FIFO during the last 4 character times
+	CharacterTimeout,
+	/// Transmitter holding register empty
+	ThrEmpty,
+	/// Clear to send, data set ready, ring indicator or data carrier detect changed
+	ModemStatus,
+	/// No interrupt pending
+	None,
+}
+
+impl InterruptCause {
+	fn from_bits(bits: u8) -> Self {
+		if bits & 0x01 != 0 {
+			return Self::None;
+		}
+		match bits & 0x0E {
+			0x06 => Self::LineStatus,
+			0x04 => Self::ReceivedDataAvailable,
+			0x0C => Self::CharacterTimeout,
+			0x02 => Self::ThrEmpty,
+			0x00 => Self::ModemStatus,
+			_ => Self::None,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Events collected while handling an interrupt
+pub struct InterruptEvents {
+	/// Received data is available in the receiver buffer register or FIFO
+	pub rx_ready: bool,
+	/// Received data below the trigger level was left in the receiver FIFO for 4 character
+	/// times, so that the FIFO must be drained without waiting for more data
+	pub rx_timeout: bool,
+	/// The transmitter holding register is empty
+	pub tx_empty: bool,
+	/// A character was lost because the receiver buffer was full
+	pub overrun_error: bool,
+	/// A character was received with a wrong parity
+	pub parity_error: bool,
+	/// A character was received without a valid stop bit
+	pub framing_error: bool,
+	/// A break condition was detected on the receive line
+	pub break_interrupt: bool,
+	/// Modem status if a modem status interrupt was pending
+	pub modem_status: Option<ModemStatus>,
+}
+
+impl InterruptEvents {
+	/// Returns whether an overrun, parity or framing error or a break condition was reported.
+	pub fn has_error(&self) -> bool {
+		self.overrun_error || self.parity_error || self.framing_error || self.break_interrupt
+	}
+
+	/// Adds the events of `other`, keeping its modem status if any.
+	pub(crate) fn merge(&mut self, other: Self) {
+		self.rx_ready |= other.rx_ready;
+		self.rx_timeout |= other.rx_timeout;
+		self.tx_empty |= other.tx_empty;
+		self.overrun_error |= other.overrun_error;
+		self.parity_error |= other.parity_error;
+		self.framing_error |= other.framing_error;
+		self.break_interrupt |= other.break_interrupt;
+		self.modem_status = other.modem_status.or(self.modem_status);
+	}
+}
+
+/// Maximum number of peripherals that can be claimed with [`Uart::take`]
+#[cfg(target_has_atomic = "ptr")]
+const MAX_CLAIMED: usize = 8;
+
+/// Base addresses of the peripherals claimed with [`Uart::take`], 0 for the free slots
+#[cfg(target_has_atomic = "ptr")]
+static CLAIMED: [AtomicUsize; MAX_CLAIMED] = [const { AtomicUsize::new(0) }; MAX_CLAIMED];
+
+/// Claims the peripheral at `base_address`, returning `false` if it is already claimed, if
+/// there is no free slot, or if the base address is 0.
+#[cfg(target_has_atomic = "ptr")]
+fn claim(base_address: usize) -> bool {
+	if base_address == 0 {
+		return false;
+	}
+	for slot in CLAIMED.iter() {
+		match slot.compare_exchange(0, base_address, Ordering::AcqRel, Ordering::Acquire) {
+			Ok(_) => return true,
+			Err(claimed) if claimed == base_address => return false,
+			Err(_) => {}
+		}
+	}
+	false
+}
+
+/// Test pattern sent by [`Uart::self_test`]
+const SELF_TEST_PATTERN: [u8; 4] = [0x55, 0xAA, 0x00, 0xFF];
+
+/// Number of line status register reads after which [`Uart::self_test`] gives up waiting
+const SELF_TEST_SPINS: usize = 1_000_000;
+
+/// Number of bytes written by [`Uart::measure_fifo_depth`]
+const FIFO_MEASURE_BYTES: usize = 256;
+
+/// Number of consecutive probe characters [`Uart::autobaud`] must receive with a divisor
+const AUTOBAUD_MATCHES: usize = 2;
+
+/// Number of times a line control register write ignored by a busy DesignWare peripheral is
+/// retried
+#[cfg(feature = "dw-apb")]
+const LCR_RETRIES: usize = 1000;
+
+/// Spacing between the registers of two ports of a PCI serial card, in bytes or I/O ports
+const PCI_PORT_SPACING: usize = 8;
+
 #[repr(u16)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-/// Divisor for setting the baud rate
+/// Divisor for setting the baud rate, with a 1.8432 MHz input clock
+///
+/// For other input clocks, see [`BaudRate`] and [`baud_divisor`].
 pub enum Divisor {
 	BAUD50 = 0x09_00,
 	BAUD300 = 0x01_80,
@@ -85,18 +806,577 @@ pub enum Divisor {
 	BAUD115200 = 0x00_01,
 }
 
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Standard baud rate, for any input clock
+///
+/// The rates above 115200 bauds require an input clock faster than 1.8432 MHz, e.g. 14.7456
+/// MHz for 921600 bauds.
+pub enum BaudRate {
+	BAUD9600 = 9600,
+	BAUD19200 = 19200,
+	BAUD38400 = 38400,
+	BAUD57600 = 57600,
+	BAUD115200 = 115200,
+	BAUD230400 = 230400,
+	BAUD460800 = 460800,
+	BAUD921600 = 921600,
+}
+
+impl BaudRate {
+	/// Returns the divisor generating the baud rate closest to this one from an input clock of
+	/// `clock_hz` Hz (see [`baud_divisor`]).
+	pub const fn divisor(self, clock_hz: u32) -> u16 {
+		baud_divisor(clock_hz, self as u32)
+	}
+}
+
+/// Returns the integer and fractional parts of the divisor generating the baud rate closest to
+/// `baud` from an input clock of `clock_hz` Hz, with a fractional divisor latch of
+/// `fractional_bits` bits, at most 8 (see [`Capabilities::fractional_divisor`]). The integer
+/// part is clamped to the range of the divisor latch.
+///
+/// # Panics
+///
+/// Panics if `baud` is zero.
+pub const fn baud_divisor_fractional(clock_hz: u32, baud: u32, fractional_bits: u8) -> (u16, u8) {
+	let divisor = (((clock_hz as u64) << fractional_bits) + 8 * baud as u64) / (16 * baud as u64);
+	let integer = divisor >> fractional_bits;
+	if integer == 0 {
+		(1, 0)
+	} else if integer > u16::MAX as u64 {
+		(u16::MAX, 0)
+	} else {
+		(
+			integer as u16,
+			(divisor & ((1 << fractional_bits) - 1)) as u8,
+		)
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Divisor generating a baud rate, returned by [`nearest_divisor`]
+pub struct BaudDivisor {
+	/// Integer part, for the divisor latch
+	pub divisor: u16,
+	/// Fractional part, for the fractional divisor latch, 0 if there is none
+	pub fraction: u8,
+	/// Baud rate generated
+	pub baud: u32,
+	/// Relative error of the baud rate generated, in parts per million, positive if it is too
+	/// fast
+	pub error_ppm: i32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Error returned by [`nearest_divisor`] when the error of the baud rate exceeds the tolerance
+pub struct BaudRateError {
+	/// Divisor generating the closest baud rate
+	pub nearest: BaudDivisor,
+}
+
+/// Returns the divisor generating the baud rate closest to `baud` from an input clock of
+/// `clock_hz` Hz, with a fractional divisor latch of `fractional_bits` bits, at most 8 (see
+/// [`baud_divisor_fractional`]), and the error of the baud rate generated. Returns an error if
+/// `max_error_ppm` is given and the absolute error exceeds it, in parts per million.
+///
+/// # Panics
+///
+/// Panics if `baud` is zero.
+pub const fn nearest_divisor(
+	clock_hz: u32,
+	baud: u32,
+	fractional_bits: u8,
+	max_error_ppm: Option<u32>,
+) -> core::result::Result<BaudDivisor, BaudRateError> {
+	let (divisor, fraction) = baud_divisor_fractional(clock_hz, baud, fractional_bits);
+	let scaled = ((divisor as u64) << fractional_bits) | fraction as u64;
+	let generated = (((clock_hz as u64) << fractional_bits) + 8 * scaled) / (16 * scaled);
+	let error_ppm = (generated as i64 - baud as i64) * 1_000_000 / baud as i64;
+	let nearest = BaudDivisor {
+		divisor,
+		fraction,
+		baud: generated as u32,
+		error_ppm: if error_ppm > i32::MAX as i64 {
+			i32::MAX
+		} else {
+			error_ppm as i32
+		},
+	};
+	match max_error_ppm {
+		Some(max) if error_ppm.unsigned_abs() > max as u64 => Err(BaudRateError { nearest }),
+		_ => Ok(nearest),
+	}
+}
+
+fn line_control_bits(
+	word_length: WordLength,
+	stop_bits: StopBits,
+	parity: Parity,
+	break_: Break,
+) -> u8 {
+	word_length as u8 | ((stop_bits as u8) << 2) | parity as u8 | ((break_ as u8) << 6)
+}
+
+impl From<Divisor> for u16 {
+	fn from(divisor: Divisor) -> Self {
+		divisor as u16
+	}
+}
+
+/// Returns the divisor generating the baud rate closest to `baud` from an input clock of
+/// `clock_hz` Hz. The result is clamped to the range of the divisor latch.
+///
+/// # Panics
+///
+/// Panics if `baud` is zero.
+pub const fn baud_divisor(clock_hz: u32, baud: u32) -> u16 {
+	let divisor = (clock_hz as u64 + 8 * baud as u64) / (16 * baud as u64);
+	if divisor == 0 {
+		1
+	} else if divisor > u16::MAX as u64 {
+		u16::MAX
+	} else {
+		divisor as u16
+	}
+}
+
+/// Returns whether the clock prescaler must be enabled (see [`Capabilities::prescaler`]) and
+/// the divisor generating the baud rate closest to `baud` from an input clock of `clock_hz` Hz.
+/// The prescaler is only enabled if the divisor would not fit in the divisor latch otherwise.
+///
+/// # Panics
+///
+/// Panics if `baud` is zero.
+pub const fn baud_divisor_prescaled(clock_hz: u32, baud: u32) -> (bool, u16) {
+	if (clock_hz as u64 + 8 * baud as u64) / (16 * baud as u64) > u16::MAX as u64 {
+		(true, baud_divisor(clock_hz / 4, baud))
+	} else {
+		(false, baud_divisor(clock_hz, baud))
+	}
+}
+
 impl Uart {
 	/// Creates a new instance of `Uart` with the given base address.
-	pub fn new(base_address: usize) -> Self {
-		Self { base_address }
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`].
+	pub const unsafe fn new(base_address: usize) -> Self {
+		Self::with_reg_shift(base_address, 0)
+	}
+
+	/// Creates a new instance of `Uart` with the given base address, with the registers spaced
+	/// by `1 << reg_shift` bytes, as described by the `reg-shift` device tree property.
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`].
+	pub const unsafe fn with_reg_shift(base_address: usize, reg_shift: u8) -> Self {
+		Self::with_reg_io_width(base_address, reg_shift, IoWidth::U8)
+	}
+
+	/// Creates a new instance of `Uart` with the given base address, with the registers spaced
+	/// by `1 << reg_shift` bytes and accessed with `reg_io_width` wide reads and writes. Only
+	/// the low byte is significant: reads are truncated and writes are zero-extended.
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`].
+	pub const unsafe fn with_reg_io_width(
+		base_address: usize,
+		reg_shift: u8,
+		reg_io_width: IoWidth,
+	) -> Self {
+		Self::with_access(Mmio::new(base_address, reg_shift, reg_io_width))
+	}
+
+	/// Creates a new instance of `Uart` with the given base address like [`new`](Self::new), or
+	/// returns an error if the base address is 0.
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`].
+	pub const unsafe fn try_new(base_address: usize) -> core::result::Result<Self, AddressError> {
+		Self::try_with_reg_io_width(base_address, 0, IoWidth::U8)
+	}
+
+	/// Creates a new instance of `Uart` with the given register layout like
+	/// [`with_reg_io_width`](Self::with_reg_io_width), or returns an error if the base address
+	/// is 0, if the base address or the register spacing is not aligned to `reg_io_width`, or if
+	/// `reg_shift` is not less than the number of bits of an address.
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`].
+	pub const unsafe fn try_with_reg_io_width(
+		base_address: usize,
+		reg_shift: u8,
+		reg_io_width: IoWidth,
+	) -> core::result::Result<Self, AddressError> {
+		if base_address == 0 {
+			Err(AddressError::Null)
+		} else if base_address & (reg_io_width as usize - 1) != 0 {
+			Err(AddressError::Misaligned)
+		} else {
+			match 1usize.checked_shl(reg_shift as u32) {
+				Some(spacing) if spacing >= reg_io_width as usize => {}
+				Some(_) => return Err(AddressError::Misaligned),
+				None => return Err(AddressError::Shift),
+			}
+			Ok(Self::with_reg_io_width(
+				base_address,
+				reg_shift,
+				reg_io_width,
+			))
+		}
+	}
+
+	/// Creates a new instance of `Uart` for the port `index`, starting at 0, of a PCI serial card
+	/// whose ports are in the memory BAR mapped at `bar_address`, spaced by 8 bytes, or returns
+	/// `None` if the address of the last register of the port overflows.
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`], with `bar_address` mapped with the registers of the port.
+	pub const unsafe fn from_pci_bar(bar_address: usize, index: usize) -> Option<Self> {
+		match index.checked_mul(PCI_PORT_SPACING) {
+			Some(offset) => match bar_address.checked_add(offset) {
+				Some(base_address) if base_address.checked_add(PCI_PORT_SPACING - 1).is_some() => {
+					Some(Self::new(base_address))
+				}
+				_ => None,
+			},
+			None => None,
+		}
+	}
+
+	/// Creates a new instance of `Uart` from the properties of a device tree node, or returns
+	/// `None` if the register shift or the access width is invalid, or if the registers are
+	/// spaced by less than the access width. The peripheral can then be initialized with
+	/// [`DtProperties::config`].
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`].
+	pub const unsafe fn from_dt_properties(properties: &DtProperties) -> Option<Self> {
+		match IoWidth::from_bytes(properties.reg_io_width) {
+			Some(reg_io_width)
+				if properties.reg_shift < 8
+					&& 1 << properties.reg_shift >= reg_io_width as usize =>
+			{
+				Some(Self::with_reg_io_width(
+					properties.base_address,
+					properties.reg_shift as u8,
+					reg_io_width,
+				))
+			}
+			_ => None,
+		}
+	}
+
+	/// Creates a new instance of `Uart` with the given base address, only if no instance was
+	/// created for this base address with `take` or [`take_with_reg_io_width`] before. Claims
+	/// are never released, and at most 8 peripherals can be claimed.
+	///
+	/// [`take_with_reg_io_width`]: Self::take_with_reg_io_width
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`]. The claim only guarantees that no other instance is created with
+	/// `take` for the same base address.
+	#[cfg(target_has_atomic = "ptr")]
+	pub unsafe fn take(base_address: usize) -> Option<Self> {
+		Self::take_with_reg_io_width(base_address, 0, IoWidth::U8)
+	}
+
+	/// Same as [`take`](Self::take), with the register layout of
+	/// [`with_reg_io_width`](Self::with_reg_io_width).
+	///
+	/// # Safety
+	///
+	/// See [`take`](Self::take).
+	#[cfg(target_has_atomic = "ptr")]
+	pub unsafe fn take_with_reg_io_width(
+		base_address: usize,
+		reg_shift: u8,
+		reg_io_width: IoWidth,
+	) -> Option<Self> {
+		if claim(base_address) {
+			Some(Self::with_reg_io_width(
+				base_address,
+				reg_shift,
+				reg_io_width,
+			))
+		} else {
+			None
+		}
 	}
 
 	/// Returns the base address
 	pub const fn base_address(&self) -> usize {
-		self.base_address
+		self.regs.base_address()
+	}
+
+	/// Returns the number of bits the register offsets are shifted by
+	pub const fn reg_shift(&self) -> u8 {
+		self.regs.reg_shift()
+	}
+
+	/// Returns the width of the register accesses
+	pub const fn reg_io_width(&self) -> IoWidth {
+		self.regs.reg_io_width()
+	}
+
+	/// Enables or disables the barriers ordering the register accesses with the memory
+	/// accesses, disabled by default. See [`Mmio::with_barriers`].
+	pub fn set_barriers(&mut self, barriers: bool) {
+		self.regs.set_barriers(barriers);
+	}
+
+	/// Moves the registers to `base_address`, keeping the rest of the state, e.g. once the MMU
+	/// maps the peripheral at another address than the one used at boot.
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`], with `base_address` as the new address of the registers of the same
+	/// peripheral.
+	pub unsafe fn rebase(&mut self, base_address: usize) {
+		self.regs.set_base_address(base_address);
+	}
+
+	/// Moves the registers to the address returned by `translate` for the current base
+	/// address, e.g. a physical to virtual address translation. See [`rebase`](Self::rebase).
+	///
+	/// # Safety
+	///
+	/// See [`rebase`](Self::rebase).
+	pub unsafe fn translate(&mut self, translate: impl FnOnce(usize) -> usize) {
+		self.rebase(translate(self.base_address()));
+	}
+}
+
+#[cfg(all(feature = "port-io", any(target_arch = "x86", target_arch = "x86_64")))]
+impl Uart<PortIo> {
+	/// Creates a new instance of `Uart` accessing the registers through the x86 I/O ports,
+	/// starting at `port`, e.g. [`PortIo::COM1`] to [`PortIo::COM4`] for the standard COM
+	/// ports.
+	///
+	/// # Safety
+	///
+	/// See [`PortIo::new`].
+	pub const unsafe fn with_port(port: u16) -> Self {
+		Self::with_access(PortIo::new(port))
+	}
+
+	/// Creates a new instance of `Uart` for the port `index`, starting at 0, of a PCI serial card
+	/// whose ports are in the I/O BAR starting at `bar_port`, spaced by 8 ports, or returns
+	/// `None` if the port number of the last register overflows. For a memory BAR, use
+	/// [`Uart::from_pci_bar`].
+	///
+	/// # Safety
+	///
+	/// See [`PortIo::new`].
+	pub const unsafe fn from_pci_io_bar(bar_port: u16, index: u16) -> Option<Self> {
+		match index.checked_mul(PCI_PORT_SPACING as u16) {
+			Some(offset) => match bar_port.checked_add(offset) {
+				Some(port) if port.checked_add(PCI_PORT_SPACING as u16 - 1).is_some() => {
+					Some(Self::with_port(port))
+				}
+				_ => None,
+			},
+			None => None,
+		}
+	}
+}
+
+impl<const BASE: usize, const REG_SHIFT: u8> Uart<MmioAt<BASE, REG_SHIFT>> {
+	/// Creates a new instance of `Uart` at the base address `BASE`, with the registers spaced by
+	/// `1 << REG_SHIFT` bytes. The register access is zero-sized, so only the state kept by
+	/// `Uart` is stored. See [`UartAt`] for a zero-sized alternative.
+	///
+	/// # Safety
+	///
+	/// See [`MmioAt::new`].
+	pub const unsafe fn at() -> Self {
+		Self::with_access(MmioAt::new())
+	}
+}
+
+impl<R: RegisterAccess> Uart<R> {
+	/// Creates a new instance of `Uart` accessing the registers through `regs`. This is safe as
+	/// the safety requirements are upheld when creating `regs`.
+	pub const fn with_access(regs: R) -> Self {
+		Self {
+			regs,
+			fcr: AtomicU8::new(0),
+			lsr_errors: AtomicU8::new(0),
+			acr: AtomicU8::new(0),
+			capabilities: Capabilities::NONE,
+			wait: &Spin,
+			rs485: None,
+			newline: Newline::NONE,
+			stats: Counters::new(),
+			error_hook: None,
+			pc_compatible: false,
+			write_hook: None,
+		}
+	}
+
+	/// Returns the register access.
+	///
+	/// # Safety
+	///
+	/// The register access must only be used to access the registers as allowed by
+	/// [`read_register`](Self::read_register).
+	pub unsafe fn regs(&self) -> &R {
+		&self.regs
+	}
+
+	/// Returns the value read in `reg`, e.g. to access model-specific bits. The line control
+	/// register is left unchanged, so the caller selects between the registers sharing an
+	/// offset, e.g. by setting DLAB.
+	///
+	/// # Safety
+	///
+	/// If `reg` is not one of the standard registers at offsets 0 to 7, the peripheral must
+	/// implement it, and it must be accessible through the register access like the standard
+	/// ones.
+	pub unsafe fn read_register(&self, reg: Register) -> u8 {
+		self.regs.read(reg)
+	}
+
+	/// Writes `value` in `reg`, e.g. to access model-specific bits. The driver is not aware of
+	/// the write: a value written in the FIFO control register is not reflected by
+	/// [`fifo_depth`](Self::fifo_depth) and is overwritten by the next FIFO configuration.
+	///
+	/// # Safety
+	///
+	/// See [`read_register`](Self::read_register).
+	pub unsafe fn write_register(&self, reg: Register, value: u8) {
+		self.regs.write(reg, value);
+	}
+
+	/// Returns a snapshot of the registers, for debugging. Reading the registers has the usual
+	/// side effects: a pending transmitter holding register empty interrupt, the line status
+	/// errors and the modem status changes are cleared. DLAB must be cleared.
+	pub fn dump_registers(&self) -> RegisterDump {
+		let lcr = self.regs.read(Register::LCR);
+		RegisterDump {
+			ier: self.regs.read(Register::IER),
+			iir: self.regs.read(Register::IIR),
+			lcr,
+			mcr: self.regs.read(Register::MCR),
+			lsr: self.read_lsr(),
+			msr: self.regs.read(Register::MSR),
+			scr: self.regs.read(Register::SCR),
+			divisor: self.read_divisor(),
+		}
+	}
+
+	/// Sets the strategy called by the blocking operations while the peripheral is not ready,
+	/// [`Spin`] by default.
+	///
+	/// The strategy is called between each poll of the peripheral by all the blocking
+	/// operations, including the writes of long messages, so it can also feed a watchdog:
+	///
+	/// ```
+	/// # use ns16550a::Uart;
+	/// fn feed_watchdog() {
+	///     // Write to the watchdog, then spin
+	///     core::hint::spin_loop();
+	/// }
+	///
+	/// # fn configure(uart: &mut Uart) {
+	/// uart.set_wait_strategy(&feed_watchdog);
+	/// # }
+	/// ```
+	pub fn set_wait_strategy(&mut self, wait: &'static dyn WaitStrategy) {
+		self.wait = wait;
+	}
+
+	/// Sets the hook called by [`handle_interrupt`](Self::handle_interrupt) with the events
+	/// collected when an overrun, parity or framing error or a break condition was reported,
+	/// none by default. The hook runs in the interrupt handler and should return quickly.
+	pub fn set_error_hook(&mut self, hook: Option<&'static (dyn Fn(InterruptEvents) + Sync)>) {
+		self.error_hook = hook;
+	}
+
+	/// Sets whether the interrupt line is gated by the user output 2, as on PC compatibles,
+	/// `false` by default. When set, the interrupt line is enabled with
+	/// [`enable_irq_output`](Self::enable_irq_output) by [`set_ier`](Self::set_ier) once an
+	/// interrupt is enabled, and therefore by [`BufferedUart::enable`] and [`AsyncUart::enable`].
+	pub fn set_pc_compatible(&mut self, pc_compatible: bool) {
+		self.pc_compatible = pc_compatible;
+	}
+
+	/// Sets the hook called by [`write_bytes`](Self::write_bytes), and therefore the [`Write`]
+	/// implementation, between the bytes written, none by default. The hook is given the number
+	/// of bytes written so far, and returns whether the write continues, so it can poll other
+	/// devices, yield or abort the write.
+	pub fn set_write_hook(&mut self, hook: Option<&'static (dyn Fn(usize) -> bool + Sync)>) {
+		self.write_hook = hook;
+	}
+
+	/// Sets the newline translation, [`Newline::NONE`] by default.
+	pub fn set_newline(&mut self, newline: Newline) {
+		self.newline = newline;
+	}
+
+	/// Enables or disables RS-485 half-duplex transmission, disabled by default.
+	///
+	/// When enabled, [`write_bytes`](Self::write_bytes), and therefore the [`Write`]
+	/// implementation, sets request to send according to `rs485` before writing, and waits for
+	/// the transmitter to be empty after the last byte before releasing it. Request to send is
+	/// released immediately when enabling. [`BufferedUart`] and [`AsyncUart`] ignore this
+	/// setting when transmitting.
+	pub fn set_rs485(&mut self, rs485: Option<Rs485>) {
+		self.rs485 = rs485;
+		if let Some(rs485) = rs485 {
+			self.set_rts(!rs485.rts_on_send);
+		}
+	}
+
+	/// Calls the wait strategy until `ready` returns `true`.
+	pub(crate) fn wait_until(&self, ready: impl Fn() -> bool) {
+		while !ready() {
+			self.wait.wait();
+		}
+	}
+
+	/// Calls `poll` at most `spins` times until it returns `Some`, calling the wait strategy
+	/// between the calls.
+	fn wait_for<T>(&self, spins: usize, mut poll: impl FnMut() -> Option<T>) -> Option<T> {
+		(0..spins).find_map(|_| {
+			let value = poll();
+			if value.is_none() {
+				self.wait.wait();
+			}
+			value
+		})
+	}
+
+	/// Returns the capabilities of the peripheral
+	pub const fn capabilities(&self) -> Capabilities {
+		self.capabilities
+	}
+
+	/// Sets the capabilities of the peripheral, enabling the corresponding features.
+	///
+	/// # Safety
+	///
+	/// If [`Capabilities::fractional_divisor`] is not 0, the peripheral must implement the
+	/// fractional divisor latch, and it must be accessible through the register access like
+	/// the standard registers, as it is written by
+	/// [`set_baud_fractional`](Self::set_baud_fractional).
+	pub unsafe fn set_capabilities(&mut self, capabilities: Capabilities) {
+		self.capabilities = capabilities;
 	}
 
-	/// Initializes the UART peripheral with the given parameters.
+	/// Initializes the UART peripheral with the given parameters. `divisor` is either a
+	/// [`Divisor`] or a raw divisor latch value.
+	#[deprecated(since = "0.5.0", note = "use `init_with` instead")]
+	#[allow(clippy::too_many_arguments, deprecated)]
 	pub fn init(
 		&self,
 		word_length: WordLength,
@@ -106,34 +1386,234 @@ impl Uart {
 		stick_parity: StickParity,
 		break_: Break,
 		dma_mode: DMAMode,
-		divisor: Divisor,
+		divisor: impl Into<u16>,
 	) {
-		self.set_lcr(
+		self.init_with(&Config {
 			word_length,
 			stop_bits,
-			parity_bit,
-			parity_select,
-			stick_parity,
+			parity: Parity::from_legacy(parity_bit, parity_select, stick_parity),
 			break_,
-			DLAB::SET,
+			dma_mode,
+			fifo: true,
+			divisor: divisor.into(),
+		});
+	}
+
+	/// Initializes the UART peripheral with the given configuration.
+	///
+	/// If the FIFOs are enabled but the interrupt identification register does not report them
+	/// as enabled afterwards, as on a 16450 or on a 16550 with a broken FIFO, they are disabled
+	/// again so that the peripheral operates one byte at a time.
+	pub fn init_with(&self, config: &Config) {
+		let lcr = line_control_bits(
+			config.word_length,
+			config.stop_bits,
+			config.parity,
+			config.break_,
 		);
-		self.set_fcr(dma_mode);
-		let ptr = (self.base_address) as *mut u16;
-		unsafe {
-			ptr.write_volatile(divisor as u16);
+		self.write_lcr(lcr | 0x80);
+		if config.fifo {
+			self.set_fcr(config.dma_mode);
+		} else {
+			self.disable_fifo();
 		}
-		self.set_lcr(
-			word_length,
-			stop_bits,
-			parity_bit,
-			parity_select,
-			stick_parity,
-			break_,
-			DLAB::CLEAR,
-		);
+		self.write_divisor(config.divisor);
+		self.write_lcr(lcr);
+		if config.fifo {
+			self.check_fifo();
+		}
+	}
+
+	/// Enables the FIFOs, keeping the line control register and the divisor latch as programmed
+	/// by the firmware or the bootloader, enables the interrupts `ier` with
+	/// [`set_ier`](Self::set_ier), and returns the resulting configuration. The FIFOs are
+	/// disabled again if they are absent or broken, like with [`init_with`](Self::init_with).
+	/// DLAB is cleared.
+	pub fn init_from_hardware(&self, ier: InterruptEnable) -> Config {
+		self.write_lcr(self.regs.read(Register::LCR) & !0x80);
+		self.set_fcr(DMAMode::MODE0);
+		self.check_fifo();
+		self.set_ier(ier);
+		self.current_config()
+	}
+
+	/// Disables the FIFOs if the interrupt identification register does not report them as
+	/// enabled.
+	fn check_fifo(&self) {
+		if self.regs.read(Register::IIR) & 0xC0 != 0xC0 {
+			self.disable_fifo();
+		}
+	}
+
+	/// Changes the configuration while the peripheral is in use, without corrupting the
+	/// characters being transmitted.
+	///
+	/// The interrupts are disabled, the written data is sent, and the peripheral is initialized
+	/// with `config` like with [`init_with`](Self::init_with). The characters received so far,
+	/// which may have been corrupted by the change, and the pending errors are then discarded,
+	/// and the interrupt enable register is restored.
+	pub fn reconfigure(&self, config: &Config) {
+		let ier = self.ier();
+		self.set_ier(InterruptEnable::default());
+		self.flush();
+		self.init_with(config);
+		self.clear_rx_fifo();
+		self.regs.read(Register::RBR);
+		self.read_lsr();
+		self.lsr_errors.store(0, Ordering::Relaxed);
+		self.set_ier(ier);
+	}
+
+	/// Disables the FIFOs, leaving the FIFO control register at 0.
+	fn disable_fifo(&self) {
+		self.set_fifo_control(FifoControl {
+			enable: false,
+			..FifoControl::default()
+		});
+	}
+
+	/// Returns the configuration currently programmed in the line control register and the
+	/// divisor latch. As the FIFO control register is write-only, the DMA mode is the one last
+	/// set through this handle.
+	pub fn current_config(&self) -> Config {
+		let lcr = self.regs.read(Register::LCR);
+		let divisor = self.read_divisor();
+		Config {
+			word_length: match lcr & 0x03 {
+				0 => WordLength::FIVE,
+				1 => WordLength::SIX,
+				2 => WordLength::SEVEN,
+				_ => WordLength::EIGHT,
+			},
+			stop_bits: if lcr & 0x04 == 0 {
+				StopBits::ONE
+			} else {
+				StopBits::TWO
+			},
+			parity: Parity::from_bits(lcr),
+			break_: if lcr & 0x40 == 0 {
+				Break::DISABLE
+			} else {
+				Break::ENABLE
+			},
+			dma_mode: if self.fcr.load(Ordering::Relaxed) & 0x08 == 0 {
+				DMAMode::MODE0
+			} else {
+				DMAMode::MODE1
+			},
+			fifo: self.fcr.load(Ordering::Relaxed) & 0x01 != 0,
+			divisor,
+		}
+	}
+
+	/// Sets the divisor latch, keeping the line control register unchanged. `divisor` is either
+	/// a [`Divisor`] or a raw divisor latch value.
+	pub fn set_baud(&self, divisor: impl Into<u16>) {
+		let lcr = self.regs.read(Register::LCR);
+		self.write_lcr(lcr | 0x80);
+		self.write_divisor(divisor.into());
+		self.write_lcr(lcr);
+	}
+
+	/// Sets the divisor latch and the fractional divisor latch, keeping the line control
+	/// register unchanged. The fractional part is ignored if the peripheral does not have
+	/// a fractional divisor latch (see [`Capabilities::fractional_divisor`]).
+	pub fn set_baud_fractional(&self, divisor: u16, fraction: u8) {
+		self.set_baud(divisor);
+		if self.capabilities.fractional_divisor != 0 {
+			self.regs.write(Register::DLF, fraction);
+		}
+	}
+
+	/// Initializes the UART peripheral with the given configuration like
+	/// [`init_with`](Self::init_with), and reads the divisor latch back to check that it was
+	/// written.
+	pub fn init_verified(&self, config: &Config) -> core::result::Result<(), DivisorMismatch> {
+		self.init_with(config);
+		self.verify_divisor(config.divisor)
+	}
+
+	/// Sets the divisor latch like [`set_baud`](Self::set_baud), and reads it back to check that
+	/// it was written.
+	pub fn set_baud_verified(
+		&self,
+		divisor: impl Into<u16>,
+	) -> core::result::Result<(), DivisorMismatch> {
+		let divisor = divisor.into();
+		self.set_baud(divisor);
+		self.verify_divisor(divisor)
+	}
+
+	fn verify_divisor(&self, written: u16) -> core::result::Result<(), DivisorMismatch> {
+		match self.read_divisor() {
+			read if read == written => Ok(()),
+			read => Err(DivisorMismatch { written, read }),
+		}
+	}
+
+	/// Returns the content of the divisor latch, keeping the line control register unchanged.
+	fn read_divisor(&self) -> u16 {
+		let lcr = self.regs.read(Register::LCR);
+		self.write_lcr(lcr | 0x80);
+		let divisor =
+			self.regs.read(Register::DLL) as u16 | (self.regs.read(Register::DLM) as u16) << 8;
+		self.write_lcr(lcr);
+		divisor
+	}
+
+	/// Writes `lcr` in the line control register.
+	///
+	/// DesignWare peripherals ignore the write while busy, so with the `dw-apb` feature the
+	/// register is read back and, while it differs and the UART status register reports the
+	/// peripheral as busy, the FIFOs are cleared and the write is retried.
+	fn write_lcr(&self, lcr: u8) {
+		self.regs.write(Register::LCR, lcr);
+		#[cfg(feature = "dw-apb")]
+		for _ in 0..LCR_RETRIES {
+			if self.regs.read(Register::LCR) == lcr {
+				return;
+			}
+			if self.regs.read(Register::USR) & 0x01 != 0 {
+				self.regs
+					.write(Register::FCR, self.fcr.load(Ordering::Relaxed) | 0x06);
+				self.regs.read(Register::RBR);
+			}
+			self.regs.write(Register::LCR, lcr);
+		}
+	}
+
+	fn write_divisor(&self, divisor: u16) {
+		self.regs.write(Register::DLL, divisor as u8);
+		self.regs.write(Register::DLM, (divisor >> 8) as u8);
+	}
+
+	/// Sets the line control register with the given parameters, clearing DLAB.
+	pub fn set_line_control(
+		&self,
+		word_length: WordLength,
+		stop_bits: StopBits,
+		parity: Parity,
+		break_: Break,
+	) {
+		self.write_lcr(line_control_bits(word_length, stop_bits, parity, break_));
+	}
+
+	/// Sets or clears the break condition, keeping the rest of the line control register
+	/// unchanged. While set, the transmit line is held low.
+	pub fn set_break(&self, break_: bool) {
+		let lcr = self.regs.read(Register::LCR);
+		self.write_lcr(if break_ { lcr | 0x40 } else { lcr & !0x40 });
+	}
+
+	/// Sets the break condition until the returned guard is dropped.
+	pub fn break_guard(&self) -> BreakGuard<'_, R> {
+		self.set_break(true);
+		BreakGuard { uart: self }
 	}
 
 	/// Sets the line control register with the given parameters.
+	#[deprecated(since = "0.5.0", note = "use `set_line_control` instead")]
+	#[allow(clippy::too_many_arguments, deprecated)]
 	pub fn set_lcr(
 		&self,
 		word_length: WordLength,
@@ -144,62 +1624,864 @@ impl Uart {
 		break_: Break,
 		dlab: DLAB,
 	) {
-		let ptr = (self.base_address + 3) as *mut u8;
-		unsafe {
-			ptr.write_volatile(
-				word_length as u8
-					| ((stop_bits as u8) << 2)
-					| ((parity_bit as u8) << 3)
-					| ((parity_select as u8) << 4)
-					| ((stick_parity as u8) << 5)
-					| ((break_ as u8) << 6)
-					| ((dlab as u8) << 7),
-			);
-		}
+		self.write_lcr(
+			line_control_bits(
+				word_length,
+				stop_bits,
+				Parity::from_legacy(parity_bit, parity_select, stick_parity),
+				break_,
+			) | ((dlab as u8) << 7),
+		);
 	}
 
 	/// Sets the FIFO control register with the given parameter.
 	pub fn set_fcr(&self, dma_mode: DMAMode) {
-		let ptr = (self.base_address + 2) as *mut u8;
-		unsafe {
-			ptr.write_volatile(1 | ((dma_mode as u8) << 3));
+		self.set_fifo_control(FifoControl {
+			dma_mode,
+			..FifoControl::default()
+		});
+	}
+
+	/// Sets the FIFO control register with the given configuration.
+	///
+	/// The 64 bytes FIFO enable bit of the 16750 can only be changed while DLAB is set, so if it
+	/// is set or was previously set through this handle, DLAB is temporarily set during the
+	/// write.
+	pub fn set_fifo_control(&self, fcr: FifoControl) {
+		let mut bits = fcr.bits();
+		if !self.capabilities.fifo64 {
+			bits &= !0x20;
+		}
+		let previous = self.fcr.load(Ordering::Relaxed);
+		self.fcr.store(bits & !0x06, Ordering::Relaxed);
+		if (bits | previous) & 0x20 != 0 {
+			let lcr = self.regs.read(Register::LCR);
+			self.write_lcr(lcr | 0x80);
+			self.regs.write(Register::FCR, bits);
+			self.write_lcr(lcr);
+		} else {
+			self.regs.write(Register::FCR, bits);
+		}
+	}
+
+	/// Returns the depth in bytes of the FIFOs, i.e. the number of bytes that can be written in
+	/// the transmitter FIFO once empty, according to the capabilities and the FIFO
+	/// configuration last set through this handle. Returns 1 if the FIFOs are disabled.
+	pub fn fifo_depth(&self) -> usize {
+		let fcr = self.fcr.load(Ordering::Relaxed);
+		if fcr & 0x01 == 0 {
+			1
+		} else if fcr & 0x20 != 0 {
+			64
+		} else if self.capabilities.fifo_depth != 0 {
+			self.capabilities.fifo_depth
+		} else {
+			16
+		}
+	}
+
+	/// Clears the receiver FIFO, keeping the FIFO configuration last set through this
+	/// handle.
+	pub fn clear_rx_fifo(&self) {
+		self.regs
+			.write(Register::FCR, self.fcr.load(Ordering::Relaxed) | 0x02);
+	}
+
+	/// Clears the transmitter FIFO, keeping the FIFO configuration last set through this
+	/// handle.
+	pub fn clear_tx_fifo(&self) {
+		self.regs
+			.write(Register::FCR, self.fcr.load(Ordering::Relaxed) | 0x04);
+	}
+
+	/// Sets the interrupt enable register. DLAB must be cleared. If the peripheral is PC
+	/// compatible (see [`set_pc_compatible`](Self::set_pc_compatible)) and an interrupt is
+	/// enabled, the interrupt line is also enabled with
+	/// [`enable_irq_output`](Self::enable_irq_output).
+	pub fn set_ier(&self, ier: InterruptEnable) {
+		let mut bits = ier.bits();
+		if !self.capabilities.sleep_mode {
+			bits &= !0x10;
+		}
+		self.regs.write(Register::IER, bits);
+		if self.pc_compatible && bits & 0x0F != 0 && !self.mcr().out2 {
+			self.enable_irq_output();
+		}
+	}
+
+	/// Returns the content of the interrupt enable register. DLAB must be cleared.
+	pub fn ier(&self) -> InterruptEnable {
+		InterruptEnable::from_bits(self.regs.read(Register::IER))
+	}
+
+	/// Enables the sleep mode of the 16750, keeping the rest of the interrupt enable register
+	/// unchanged. Requires [`Capabilities::sleep_mode`]. DLAB must be cleared.
+	///
+	/// The peripheral sleeps while no interrupt is pending, the receiver is idle and the
+	/// transmitter is empty. It wakes up when a start bit is received, a modem input changes or
+	/// a character is written, so with the received data interrupt enabled, an interrupt is
+	/// raised once the first character after wake-up is received.
+	pub fn enter_sleep(&self) {
+		self.set_ier(InterruptEnable {
+			sleep_mode: true,
+			..self.ier()
+		});
+	}
+
+	/// Disables the sleep mode of the 16750, keeping the rest of the interrupt enable register
+	/// unchanged. DLAB must be cleared.
+	pub fn exit_sleep(&self) {
+		self.set_ier(InterruptEnable {
+			sleep_mode: false,
+			..self.ier()
+		});
+	}
+
+	/// Sets the modem control register.
+	pub fn set_mcr(&self, mcr: ModemControl) {
+		let mut bits = mcr.bits();
+		if !self.capabilities.auto_flow_control {
+			bits &= !0x20;
+		}
+		if !self.capabilities.prescaler {
+			bits &= !0x80;
+		}
+		self.regs.write(Register::MCR, bits);
+	}
+
+	/// Enables or disables the clock prescaler dividing the input clock by 4, keeping the rest
+	/// of the modem control register unchanged. Requires [`Capabilities::prescaler`].
+	pub fn set_prescaler(&self, enabled: bool) {
+		self.set_mcr(ModemControl {
+			prescaler: enabled,
+			..self.mcr()
+		});
+	}
+
+	/// Returns the content of the modem control register.
+	pub fn mcr(&self) -> ModemControl {
+		ModemControl::from_bits(self.regs.read(Register::MCR))
+	}
+
+	/// Sets or clears the data terminal ready output.
+	pub fn set_dtr(&self, dtr: bool) {
+		self.set_mcr(ModemControl { dtr, ..self.mcr() });
+	}
+
+	/// Sets or clears the request to send output.
+	pub fn set_rts(&self, rts: bool) {
+		self.set_mcr(ModemControl { rts, ..self.mcr() });
+	}
+
+	/// Sets or clears the user output 1.
+	pub fn set_out1(&self, out1: bool) {
+		self.set_mcr(ModemControl { out1, ..self.mcr() });
+	}
+
+	/// Sets or clears the user output 2.
+	pub fn set_out2(&self, out2: bool) {
+		self.set_mcr(ModemControl { out2, ..self.mcr() });
+	}
+
+	/// Sets the user output 2, which enables the interrupt line on PC compatibles.
+	pub fn enable_irq_output(&self) {
+		self.set_out2(true);
+	}
+
+	/// Enables or disables the loopback mode.
+	pub fn set_loopback(&self, loopback: bool) {
+		self.set_mcr(ModemControl {
+			loopback,
+			..self.mcr()
+		});
+	}
+
+	/// Enables or disables the automatic flow control. Requires
+	/// [`Capabilities::auto_flow_control`].
+	pub fn set_auto_flow_control(&self, auto_flow_control: bool) {
+		self.set_mcr(ModemControl {
+			auto_flow_control,
+			..self.mcr()
+		});
+	}
+
+	/// Reads the line status register. Reading this register clears the error bits and a
+	/// pending line status interrupt.
+	pub fn line_status(&self) -> LineStatus {
+		LineStatus::from_bits(self.read_lsr())
+	}
+
+	/// Reads the modem status register. Reading this register clears the delta bits and a
+	/// pending modem status interrupt.
+	pub fn modem_status(&self) -> ModemStatus {
+		ModemStatus::from_bits(self.regs.read(Register::MSR))
+	}
+
+	/// Writes `value` in the scratch register.
+	pub fn scratch_write(&self, value: u8) {
+		self.regs.write(Register::SCR, value);
+	}
+
+	/// Returns the value read in the scratch register.
+	pub fn scratch_read(&self) -> u8 {
+		self.regs.read(Register::SCR)
+	}
+
+	/// Returns whether the scratch register is present, by writing test patterns and reading
+	/// them back. The 8250 does not have a scratch register, while the 16450 and later do. The
+	/// previous content of the register is restored.
+	pub fn has_scratch(&self) -> bool {
+		let saved = self.scratch_read();
+		let present = [0x55, 0xAA].iter().all(|&pattern| {
+			self.scratch_write(pattern);
+			self.scratch_read() == pattern
+		});
+		self.scratch_write(saved);
+		present
+	}
+
+	/// Identifies the model of the peripheral.
+	///
+	/// The FIFOs are enabled, along with the 64 bytes FIFO, and the FIFO status bits of the
+	/// interrupt identification register are checked. If FIFOs are found, the identification
+	/// registers of the 16C950 are read if it has an enhanced features register. If no FIFO is
+	/// found, the presence of the scratch register distinguishes the 16450 from the 8250. The
+	/// FIFO control register is then restored to the configuration last set through this
+	/// handle.
+	pub fn probe(&self) -> Model {
+		let lcr = self.regs.read(Register::LCR);
+		self.write_lcr(lcr | 0x80);
+		self.regs.write(Register::FCR, 0xE7);
+		self.write_lcr(lcr);
+		let iir = self.regs.read(Register::IIR);
+		let ox16c950 = iir & 0xC0 == 0xC0 && self.has_ox16c950_id(lcr);
+		self.write_lcr(lcr | 0x80);
+		self.regs
+			.write(Register::FCR, self.fcr.load(Ordering::Relaxed));
+		self.write_lcr(lcr);
+		if iir & 0xC0 == 0xC0 {
+			if ox16c950 {
+				Model::OX16C950
+			} else if iir & 0x20 != 0 {
+				Model::TL16C750
+			} else {
+				Model::NS16550A
+			}
+		} else if iir & 0x80 != 0 {
+			Model::NS16550
+		} else if self.has_scratch() {
+			Model::NS16450
+		} else {
+			Model::NS8250
+		}
+	}
+
+	/// Reads the FIFO depth of a DesignWare peripheral in the component parameter register,
+	/// records it in [`Capabilities::fifo_depth`] and returns it. Returns 0 and leaves the
+	/// capabilities unchanged if the register is not implemented or reports no FIFO.
+	#[cfg(feature = "dw-apb")]
+	pub fn probe_fifo_depth(&mut self) -> usize {
+		let depth = ((self.regs.read_u32(Register::CPR) >> 16) & 0xFF) as usize * 16;
+		if depth != 0 {
+			self.capabilities.fifo_depth = depth;
+		}
+		depth
+	}
+
+	/// Measures the depth of the receiver FIFO, with the FIFO configuration last set through
+	/// this handle, records it in [`Capabilities::fifo_depth`] if the FIFOs are enabled, and
+	/// returns it.
+	///
+	/// In loopback mode, up to 256 bytes are written without waiting, so that the bytes which
+	/// do not fit in the transmitter FIFO are lost, and the bytes received once the transmitter
+	/// is empty are counted. The peripheral must be initialized. Pending received characters
+	/// are discarded, and the interrupt enable register, the modem control register and the
+	/// divisor latch are restored afterwards.
+	pub fn measure_fifo_depth(&mut self) -> usize {
+		let ier = self.ier();
+		let mcr = self.mcr();
+		let divisor = self.current_config().divisor;
+		self.set_ier(InterruptEnable::default());
+		self.set_mcr(ModemControl {
+			loopback: true,
+			..mcr
+		});
+		self.set_baud(1u16);
+		self.clear_rx_fifo();
+		(0..FIFO_MEASURE_BYTES).for_each(|_| self.regs.write(Register::THR, 0));
+		self.wait_for(SELF_TEST_SPINS, || {
+			self.is_transmit_complete().then_some(())
+		});
+		let depth = (0..FIFO_MEASURE_BYTES)
+			.take_while(|_| self.get().is_some())
+			.count();
+		self.set_baud(divisor);
+		self.set_mcr(mcr);
+		self.set_ier(ier);
+		if depth > 1 && self.fcr.load(Ordering::Relaxed) & 0x01 != 0 {
+			self.capabilities.fifo_depth = depth;
+		}
+		depth
+	}
+
+	/// Sends a test pattern in loopback mode and checks that it is received correctly. The
+	/// peripheral must be initialized. Pending received characters are discarded, and the
+	/// interrupt enable and modem control registers are restored afterwards.
+	pub fn self_test(&self) -> core::result::Result<(), SelfTestError> {
+		let ier = self.ier();
+		let mcr = self.mcr();
+		self.set_ier(InterruptEnable::default());
+		self.set_mcr(ModemControl {
+			loopback: true,
+			..mcr
+		});
+		(0..SELF_TEST_SPINS).find(|_| self.get().is_none());
+		let result = SELF_TEST_PATTERN.iter().try_for_each(|&expected| {
+			self.wait_for(SELF_TEST_SPINS, || self.put(expected))
+				.ok_or(SelfTestError::Timeout)?;
+			let received = self
+				.wait_for(SELF_TEST_SPINS, || self.get())
+				.ok_or(SelfTestError::Timeout)?;
+			if received == expected {
+				Ok(())
+			} else {
+				Err(SelfTestError::Mismatch { expected, received })
+			}
+		});
+		self.set_mcr(mcr);
+		self.set_ier(ier);
+		result
+	}
+
+	/// Detects the baud rate of a remote repeatedly sending `probe`, e.g. `b'U'`, by trying each
+	/// divisor of `divisors` until the probe character is received without error twice in a
+	/// row. The line status register is polled at most `spins` times for each character.
+	/// Returns the divisor found, which is kept, or `None` after restoring the previous divisor.
+	pub fn autobaud(&self, probe: u8, divisors: &[u16], spins: usize) -> Option<u16> {
+		let previous = self.current_config().divisor;
+		let divisor = divisors.iter().copied().find(|&divisor| {
+			self.set_baud(divisor);
+			self.clear_rx_fifo();
+			(0..AUTOBAUD_MATCHES).all(|_| {
+				let received = self.wait_for(spins, || match self.get_with_error() {
+					Ok(None) => None,
+					result => Some(result),
+				});
+				received == Some(Ok(Some(probe)))
+			})
+		});
+		if divisor.is_none() {
+			self.set_baud(previous);
+		}
+		divisor
+	}
+
+	/// Reads the interrupt identification register and returns the cause of the highest
+	/// priority pending interrupt. Reading this register clears a pending transmitter holding
+	/// register empty interrupt.
+	///
+	/// With the `dw-apb` feature, a DesignWare busy detect interrupt, raised when the line
+	/// control register is written while the peripheral is busy, is acknowledged by reading the
+	/// UART status register, and the next pending interrupt is returned.
+	pub fn interrupt_id(&self) -> InterruptCause {
+		let iir = self.regs.read(Register::IIR);
+		#[cfg(feature = "dw-apb")]
+		let iir = if iir & 0x0F == 0x07 {
+			self.regs.read(Register::USR);
+			self.regs.read(Register::IIR)
+		} else {
+			iir
+		};
+		InterruptCause::from_bits(iir)
+	}
+
+	/// Acknowledges the pending interrupts and returns the corresponding events.
+	///
+	/// Line status and modem status interrupts are cleared by reading the line status and modem
+	/// status registers, and transmitter holding register empty interrupts by reading the
+	/// interrupt identification register. Received data interrupts are only cleared once the
+	/// received data has been read, so when `rx_ready` is set, the caller must drain the receiver
+	/// with [`get`](Self::get). If errors were reported, the events are also passed to the hook
+	/// set with [`set_error_hook`](Self::set_error_hook).
+	pub fn handle_interrupt(&self) -> InterruptEvents {
+		let mut events = InterruptEvents::default();
+		loop {
+			match self.interrupt_id() {
+				InterruptCause::None => break,
+				InterruptCause::LineStatus => {
+					let lsr = self.line_status();
+					events.overrun_error |= lsr.overrun_error;
+					events.parity_error |= lsr.parity_error;
+					events.framing_error |= lsr.framing_error;
+					events.break_interrupt |= lsr.break_interrupt;
+					events.rx_ready |= lsr.data_ready;
+				}
+				InterruptCause::ReceivedDataAvailable => {
+					events.rx_ready = true;
+					break;
+				}
+				InterruptCause::CharacterTimeout => {
+					events.rx_ready = true;
+					events.rx_timeout = true;
+					break;
+				}
+				InterruptCause::ThrEmpty => events.tx_empty = true,
+				InterruptCause::ModemStatus => events.modem_status = Some(self.modem_status()),
+			}
 		}
+		if let Some(hook) = self.error_hook.filter(|_| events.has_error()) {
+			hook(events);
+		}
+		events
 	}
 
 	/// If the transmitter holding register is empty, writes `c` in the transmitter holding register, and returns `c`. Otherwise returns `None`.
 	pub fn put(&self, c: u8) -> Option<u8> {
-		let ptr = self.base_address as *mut u8;
-
 		// If THR is not empty
-		let ptr_data_ready = (self.base_address + 5) as *mut u8;
-		if unsafe { ptr_data_ready.read_volatile() & 0x20 == 0 } {
+		if !self.is_thr_empty() {
 			return None;
 		}
 
-		unsafe {
-			ptr.write_volatile(c);
-		}
+		self.write_thr(c);
 		Some(c)
 	}
 
+	/// Waits for the transmitter holding register to be empty, and writes `c` in it.
+	pub fn put_blocking(&self, c: u8) {
+		self.wait_until(|| self.is_thr_empty());
+		self.write_thr(c);
+	}
+
+	/// Writes `bytes`, waiting for the transmitter holding register to be empty before writing
+	/// each burst of bytes filling the transmitter FIFO, and returns the number of bytes
+	/// written. With RS-485 enabled (see [`set_rs485`](Self::set_rs485)), request to send is
+	/// held during the transmission. Fewer bytes are written if the write is aborted by the
+	/// hook set with [`set_write_hook`](Self::set_write_hook).
+	pub fn write_bytes(&self, bytes: &[u8]) -> usize {
+		self.begin_transmission();
+		let written = self.write_bursts(bytes, 0);
+		self.end_transmission();
+		written
+	}
+
+	/// Writes `bytes` like [`write_bytes`](Self::write_bytes) without the RS-485 handling, where
+	/// `written` bytes of the same transmission were already written, and returns the total.
+	fn write_bursts(&self, bytes: &[u8], mut written: usize) -> usize {
+		'write: for burst in bytes.chunks(self.fifo_depth()) {
+			self.wait_until(|| self.is_thr_empty());
+			for &c in burst {
+				if written != 0 && self.write_hook.is_some_and(|hook| !hook(written)) {
+					break 'write;
+				}
+				self.write_thr(c);
+				written += 1;
+			}
+		}
+		written
+	}
+
+	/// Sets request to send before a transmission if RS-485 is enabled.
+	fn begin_transmission(&self) {
+		if let Some(rs485) = self.rs485 {
+			self.set_rts(rs485.rts_on_send);
+		}
+	}
+
+	/// Waits for the transmitter to be empty and releases request to send after a transmission if
+	/// RS-485 is enabled.
+	fn end_transmission(&self) {
+		if let Some(rs485) = self.rs485 {
+			self.flush();
+			self.set_rts(!rs485.rts_on_send);
+		}
+	}
+
+	/// If the transmitter holding register is empty, writes as many bytes of `bytes` as the
+	/// transmitter FIFO can hold, and returns the number of bytes written. Otherwise returns 0.
+	pub fn write_nb(&self, bytes: &[u8]) -> usize {
+		if !self.is_thr_empty() {
+			return 0;
+		}
+		let burst = &bytes[..bytes.len().min(self.fifo_depth())];
+		burst.iter().for_each(|&c| self.write_thr(c));
+		burst.len()
+	}
+
 	/// If data ready is set, returns the value read in the receiver buffer register. Otherwise
 	/// returns `None`.
 	pub fn get(&self) -> Option<u8> {
-		let ptr = self.base_address as *mut u8;
-		let ptr_data_ready = (self.base_address + 5) as *mut u8;
-		unsafe {
-			if ptr_data_ready.read_volatile() & 1 == 0 {
-				None
+		if !self.rx_ready() {
+			None
+		} else {
+			Some(self.read_rbr())
+		}
+	}
+
+	/// Waits for data ready and returns the value read in the receiver buffer register.
+	pub fn get_blocking(&self) -> u8 {
+		self.wait_until(|| self.rx_ready());
+		self.read_rbr()
+	}
+
+	/// Returns an iterator over the received bytes, which ends once data ready is cleared.
+	pub fn incoming(&self) -> impl Iterator<Item = u8> + '_ {
+		core::iter::from_fn(move || self.get())
+	}
+
+	/// Returns an endless iterator over the received bytes, waiting for each of them.
+	pub fn incoming_blocking(&self) -> impl Iterator<Item = u8> + '_ {
+		core::iter::repeat_with(move || self.get_blocking())
+	}
+
+	/// Reads the receiver buffer register, applying the newline translation.
+	fn read_rbr(&self) -> u8 {
+		self.stats.add(Counter::RxBytes, 1);
+		self.lsr_errors.store(0, Ordering::Relaxed);
+		match self.regs.read(Register::RBR) {
+			b'\r' if self.newline.cr_to_lf => b'\n',
+			c => c,
+		}
+	}
+
+	/// Writes `s` like [`write_bytes`](Self::write_bytes), as a single transmission, applying the
+	/// newline translation, and returns an error if the write was aborted.
+	pub(crate) fn write_text(&self, s: &str) -> Result {
+		self.begin_transmission();
+		let result = self.write_lines(s);
+		self.end_transmission();
+		result
+	}
+
+	fn write_lines(&self, s: &str) -> Result {
+		let mut written = 0;
+		let mut write_all = |bytes: &[u8]| {
+			let total = self.write_bursts(bytes, written);
+			if total - written == bytes.len() {
+				written = total;
+				Ok(())
 			} else {
-				Some(ptr.read_volatile())
+				Err(core::fmt::Error)
+			}
+		};
+		if !self.newline.lf_to_crlf {
+			return write_all(s.as_bytes());
+		}
+		for (i, line) in s.split('\n').enumerate() {
+			if i != 0 {
+				write_all(b"\r\n")?;
 			}
+			write_all(line.as_bytes())?;
 		}
+		Ok(())
 	}
+
+	/// Waits for data ready and returns the value read in the receiver buffer register. The line
+	/// status register is polled at most `spins` times before `TimedOut` is returned.
+	pub fn get_timeout(&self, spins: usize) -> core::result::Result<u8, TimedOut> {
+		self.wait_for(spins, || self.get()).ok_or(TimedOut)
+	}
+
+	/// Fills `buf` with received data. The line status register is polled at most `spins` times
+	/// for each byte before `TimedOut` is returned, in which case the content of `buf` is
+	/// unspecified.
+	pub fn read_exact_timeout(
+		&self,
+		buf: &mut [u8],
+		spins: usize,
+	) -> core::result::Result<(), TimedOut> {
+		buf.iter_mut()
+			.try_for_each(|b| self.get_timeout(spins).map(|c| *b = c))
+	}
+
+	/// Sends `c` as an address character of the 9-bit multidrop protocol, with mark parity. If
+	/// the parity is changed, the transmitter is first waited to be empty.
+	pub fn send_address(&self, c: u8) {
+		self.send_multidrop(c, Parity::MARK);
+	}
+
+	/// Sends `c` as a data character of the 9-bit multidrop protocol, with space parity. If the
+	/// parity is changed, the transmitter is first waited to be empty.
+	pub fn send_data(&self, c: u8) {
+		self.send_multidrop(c, Parity::SPACE);
+	}
+
+	fn send_multidrop(&self, c: u8, parity: Parity) {
+		let lcr = self.regs.read(Register::LCR);
+		if lcr & 0x38 != parity as u8 {
+			self.flush();
+			self.write_lcr((lcr & !0x38) | parity as u8);
+		}
+		self.wait_until(|| self.is_thr_empty());
+		self.write_thr(c);
+	}
+
+	/// If data ready is set, returns the character read in the receiver buffer register as a
+	/// character of the 9-bit multidrop protocol. The 9th bit is deduced from the parity error
+	/// and the parity currently set, which must be mark or space parity: with space parity, the
+	/// address characters have a parity error, and with mark parity the data characters do.
+	pub fn get_multidrop(&self) -> Option<MultidropByte> {
+		let lsr = LineStatus::from_bits(self.read_lsr() | self.lsr_errors.load(Ordering::Relaxed));
+		if !lsr.data_ready {
+			return None;
+		}
+		let mark = self.regs.read(Register::LCR) & 0x38 == Parity::MARK as u8;
+		self.lsr_errors.store(0, Ordering::Relaxed);
+		let c = self.regs.read(Register::RBR);
+		self.stats.add(Counter::RxBytes, 1);
+		Some(if lsr.parity_error != mark {
+			MultidropByte::Address(c)
+		} else {
+			MultidropByte::Data(c)
+		})
+	}
+
+	/// Waits until the transmitter holding register and the transmitter shift register are both
+	/// empty, i.e. until all the written data has been sent.
+	pub fn flush(&self) {
+		self.wait_until(|| self.try_flush());
+	}
+
+	/// Returns whether the transmitter holding register and the transmitter shift register are
+	/// both empty, i.e. whether all the written data has been sent.
+	pub fn try_flush(&self) -> bool {
+		self.is_transmit_complete()
+	}
+
+	/// Returns whether data ready is set, i.e. whether [`get`](Self::get) returns a character.
+	pub fn rx_ready(&self) -> bool {
+		self.read_lsr() & 0x01 != 0
+	}
+
+	/// Returns whether a character can be written, i.e. whether the transmitter holding
+	/// register is empty. See [`is_thr_empty`](Self::is_thr_empty).
+	pub fn tx_ready(&self) -> bool {
+		self.is_thr_empty()
+	}
+
+	/// Returns whether the transmitter holding register is empty, i.e. whether the transmitter
+	/// FIFO can be filled.
+	pub fn is_thr_empty(&self) -> bool {
+		self.read_lsr() & 0x20 != 0
+	}
+
+	/// Returns whether the transmitter holding register and the transmitter shift register are
+	/// both empty, i.e. whether the line is idle.
+	pub fn is_transmit_complete(&self) -> bool {
+		self.read_lsr() & 0x40 != 0
+	}
+
+	/// Reads the line status register, counting the errors it reports and keeping them until
+	/// the receiver buffer register is read.
+	fn read_lsr(&self) -> u8 {
+		let lsr = self.regs.read(Register::LSR);
+		self.stats.add_errors(lsr);
+		if lsr & 0x1E != 0 {
+			let errors = self.lsr_errors.load(Ordering::Relaxed);
+			self.lsr_errors
+				.store(errors | lsr & 0x1E, Ordering::Relaxed);
+		}
+		lsr
+	}
+
+	/// Writes `c` in the transmitter holding register, counting it.
+	pub(crate) fn write_thr(&self, c: u8) {
+		self.regs.write(Register::THR, c);
+		self.stats.add(Counter::TxBytes, 1);
+	}
+
+	/// Moves the received data to `buf` while data ready is set, and returns the number of bytes
+	/// read.
+	pub fn read_available(&self, buf: &mut [u8]) -> usize {
+		buf.iter_mut()
+			.map_while(|b| self.get().map(|c| *b = c))
+			.count()
+	}
+
+	/// If data ready is set, returns the value read in the receiver buffer register. Otherwise
+	/// returns `None`. If the received character has a break, framing or parity error, it is
+	/// discarded and the error is returned instead. An overrun error is reported without
+	/// consuming the character, which is still valid and returned by the next call.
+	///
+	/// The errors are reported even if the line status register was read in between by other
+	/// operations, e.g. by the transmitter half polling for space in the transmitter FIFO, until
+	/// the character they apply to is read.
+	pub fn get_with_error(&self) -> core::result::Result<Option<u8>, RxError> {
+		let lsr = LineStatus::from_bits(self.read_lsr() | self.lsr_errors.load(Ordering::Relaxed));
+		self.lsr_errors.store(0, Ordering::Relaxed);
+		let error = if lsr.break_interrupt {
+			Some(RxError::Break)
+		} else if lsr.framing_error {
+			Some(RxError::Framing)
+		} else if lsr.parity_error {
+			Some(RxError::Parity)
+		} else {
+			None
+		};
+		match error {
+			Some(error) => {
+				if lsr.data_ready {
+					self.regs.read(Register::RBR);
+				}
+				Err(error)
+			}
+			None if lsr.overrun_error => Err(RxError::Overrun),
+			None if lsr.data_ready => Ok(Some(self.read_rbr())),
+			None => Ok(None),
+		}
+	}
+}
+
+#[derive(Debug)]
+/// Guard holding the break condition, returned by [`Uart::break_guard`]
+///
+/// The break condition is cleared when the guard is dropped.
+pub struct BreakGuard<'a, R: RegisterAccess> {
+	uart: &'a Uart<R>,
 }
 
-impl Write for Uart {
+impl<R: RegisterAccess> Drop for BreakGuard<'_, R> {
+	fn drop(&mut self) {
+		self.uart.set_break(false);
+	}
+}
+
+impl<R: RegisterAccess> Write for Uart<R> {
 	fn write_str(&mut self, s: &str) -> Result {
-		s.bytes().for_each(|c| while self.put(c) == None {});
-		Ok(())
+		self.write_text(s)
+	}
+}
+
+impl<R: RegisterAccess> Write for &Uart<R> {
+	fn write_str(&mut self, s: &str) -> Result {
+		self.write_text(s)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn try_with_reg_io_width() {
+		let uart = |reg_shift, reg_io_width| unsafe {
+			Uart::try_with_reg_io_width(0x1000, reg_shift, reg_io_width)
+				.map(|uart| uart.reg_shift())
+		};
+		assert_eq!(uart(2, IoWidth::U32), Ok(2));
+		assert_eq!(uart(1, IoWidth::U32), Err(AddressError::Misaligned));
+		assert_eq!(
+			uart(usize::BITS as u8, IoWidth::U8),
+			Err(AddressError::Shift)
+		);
+		assert_eq!(uart(u8::MAX, IoWidth::U8), Err(AddressError::Shift));
+	}
+
+	#[test]
+	fn from_dt_properties() {
+		let uart = |reg_shift, reg_io_width| unsafe {
+			Uart::from_dt_properties(&DtProperties {
+				reg_shift,
+				reg_io_width,
+				..DtProperties::new(0x1000, 1_843_200)
+			})
+			.map(|uart| uart.reg_shift())
+		};
+		assert_eq!(uart(2, 4), Some(2));
+		assert_eq!(uart(0, 4), None);
+		assert_eq!(uart(8, 1), None);
+		assert_eq!(uart(0, 3), None);
+	}
+
+	#[test]
+	fn from_pci_bar() {
+		let uart = |bar_address, index| unsafe {
+			Uart::from_pci_bar(bar_address, index).map(|uart| uart.base_address())
+		};
+		assert_eq!(uart(0x1000, 2), Some(0x1010));
+		assert_eq!(uart(usize::MAX - 7, 0), Some(usize::MAX - 7));
+		assert_eq!(uart(usize::MAX - 6, 0), None);
+		assert_eq!(uart(0, usize::MAX), None);
+	}
+
+	#[cfg(all(feature = "port-io", any(target_arch = "x86", target_arch = "x86_64")))]
+	#[test]
+	fn from_pci_io_bar() {
+		let uart = |bar_port, index| unsafe {
+			Uart::from_pci_io_bar(bar_port, index).map(|uart| uart.regs.port())
+		};
+		assert_eq!(uart(0xE000, 3), Some(0xE018));
+		assert_eq!(uart(u16::MAX - 7, 0), Some(u16::MAX - 7));
+		assert_eq!(uart(u16::MAX - 15, 1), Some(u16::MAX - 7));
+		assert_eq!(uart(u16::MAX - 14, 1), None);
+		assert_eq!(uart(0, u16::MAX), None);
+	}
+
+	#[test]
+	fn baud_divisor_exact() {
+		assert_eq!(baud_divisor(1_843_200, 115_200), 1);
+		assert_eq!(baud_divisor(1_843_200, 9600), 12);
+		assert_eq!(BaudRate::BAUD9600.divisor(1_843_200), 12);
+	}
+
+	#[test]
+	fn baud_divisor_rounded() {
+		// 26.04 and 325.52
+		assert_eq!(baud_divisor(48_000_000, 115_200), 26);
+		assert_eq!(baud_divisor(50_000_000, 9600), 326);
+	}
+
+	#[test]
+	fn baud_divisor_out_of_range() {
+		assert_eq!(baud_divisor(1_843_200, 1), u16::MAX);
+		assert_eq!(baud_divisor(1_843_200, 1_000_000), 1);
+		assert_eq!(baud_divisor_prescaled(48_000_000, 40), (true, 18750));
+		assert_eq!(baud_divisor_prescaled(48_000_000, 115_200), (false, 26));
+	}
+
+	#[test]
+	#[should_panic]
+	fn baud_divisor_zero_baud() {
+		baud_divisor(1_843_200, core::hint::black_box(0));
+	}
+
+	#[test]
+	fn baud_divisor_fraction() {
+		// 26.04, 417 sixteenths
+		assert_eq!(baud_divisor_fractional(48_000_000, 115_200, 4), (26, 1));
+		assert_eq!(baud_divisor_fractional(48_000_000, 115_200, 0), (26, 0));
+		assert_eq!(baud_divisor_fractional(1_843_200, 115_200, 8), (1, 0));
+		assert_eq!(baud_divisor_fractional(1_843_200, 1, 4), (u16::MAX, 0));
+		assert_eq!(baud_divisor_fractional(1_843_200, 10_000_000, 4), (1, 0));
+	}
+
+	#[test]
+	fn nearest_divisor_error() {
+		let exact = BaudDivisor {
+			divisor: 1,
+			fraction: 0,
+			baud: 115_200,
+			error_ppm: 0,
+		};
+		assert_eq!(nearest_divisor(1_843_200, 115_200, 0, Some(0)), Ok(exact));
+		let nearest = BaudDivisor {
+			divisor: 26,
+			fraction: 0,
+			baud: 115_385,
+			error_ppm: 1605,
+		};
+		assert_eq!(nearest_divisor(48_000_000, 115_200, 0, None), Ok(nearest));
+		assert_eq!(
+			nearest_divisor(48_000_000, 115_200, 0, Some(1000)),
+			Err(BaudRateError { nearest })
+		);
+		let fractional = nearest_divisor(48_000_000, 115_200, 4, Some(1000)).unwrap();
+		assert_eq!((fractional.divisor, fractional.fraction), (26, 1));
+		assert!(fractional.error_ppm.unsigned_abs() < 1000);
+	}
+
+	#[test]
+	#[should_panic]
+	fn nearest_divisor_zero_baud() {
+		let _ = nearest_divisor(1_843_200, core::hint::black_box(0), 0, None);
 	}
 }
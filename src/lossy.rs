@@ -0,0 +1,68 @@
+//! Non-blocking lossy writer.
+
+use crate::{RegisterAccess, Uart};
+use core::fmt::{Result, Write};
+
+#[derive(Debug)]
+/// Writer dropping the bytes which do not fit in the transmitter FIFO instead of waiting,
+/// returned by [`Uart::lossy_writer`]
+///
+/// This is meant for low-priority logging, e.g. from interrupt handlers, where blocking is worse
+/// than losing characters. The newline translation is applied, and the dropped bytes are
+/// counted.
+///
+/// As the transmitter FIFO is only known to have room once it is empty, the writer keeps track
+/// of the bytes written since the transmitter holding register was last seen empty, so that the
+/// successive pieces of a formatted output fill the FIFO.
+pub struct LossyWriter<'a, R: RegisterAccess> {
+	uart: &'a Uart<R>,
+	dropped: usize,
+	/// Number of bytes which can still be written in the transmitter FIFO
+	budget: usize,
+}
+
+impl<R: RegisterAccess> Uart<R> {
+	/// Returns a writer dropping the bytes which do not fit in the transmitter FIFO.
+	pub fn lossy_writer(&self) -> LossyWriter<'_, R> {
+		LossyWriter {
+			uart: self,
+			dropped: 0,
+			budget: 0,
+		}
+	}
+}
+
+impl<R: RegisterAccess> LossyWriter<'_, R> {
+	/// Returns the number of bytes dropped since the creation of the writer.
+	pub fn dropped(&self) -> usize {
+		self.dropped
+	}
+
+	fn write_lossy(&mut self, bytes: &[u8]) {
+		if self.uart.is_thr_empty() {
+			self.budget = self.uart.fifo_depth();
+		}
+		let written = bytes.len().min(self.budget);
+		bytes[..written]
+			.iter()
+			.for_each(|&c| self.uart.write_thr(c));
+		self.budget -= written;
+		self.dropped += bytes.len() - written;
+	}
+}
+
+impl<R: RegisterAccess> Write for LossyWriter<'_, R> {
+	fn write_str(&mut self, s: &str) -> Result {
+		if !self.uart.newline.lf_to_crlf {
+			self.write_lossy(s.as_bytes());
+			return Ok(());
+		}
+		for (i, line) in s.split('\n').enumerate() {
+			if i != 0 {
+				self.write_lossy(b"\r\n");
+			}
+			self.write_lossy(line.as_bytes());
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,453 @@
+//! Emulated registers for testing the driver on the host.
+
+use crate::{Register, RegisterAccess, RxError};
+use core::cell::RefCell;
+
+/// Depth of the emulated FIFOs
+const FIFO_SIZE: usize = 16;
+/// Number of transmitted bytes kept by the emulated registers
+const TX_LOG_SIZE: usize = 256;
+
+#[derive(Debug)]
+struct State {
+	/// Received bytes not read yet, oldest first
+	rx: [u8; FIFO_SIZE],
+	/// Parity, framing and break error bits of the received bytes, reported by the line status
+	/// register once the byte is the oldest one
+	rx_errors: [u8; FIFO_SIZE],
+	rx_len: usize,
+	/// Transmitted bytes not taken yet, oldest first
+	tx: [u8; TX_LOG_SIZE],
+	tx_len: usize,
+	/// Whether a received byte was lost since the last line status register read
+	overrun: bool,
+	/// Whether the transmitter holding register empty interrupt is pending
+	thre_pending: bool,
+	/// Content of the registers
+	ier: u8,
+	fcr: u8,
+	lcr: u8,
+	mcr: u8,
+	msr: u8,
+	scr: u8,
+	dll: u8,
+	dlm: u8,
+}
+
+#[derive(Debug)]
+/// Emulated NS16550A registers, for testing code using [`Uart`](crate::Uart) on the host
+///
+/// Transmission is instantaneous: the transmitted bytes are logged, and looped back to the
+/// receiver in loopback mode. Received bytes are injected with [`receive`](Self::receive) and
+/// are lost, setting the overrun error, once the receiver FIFO is full. The registers are
+/// decoded by offset like on a NS16550A, so the registers specific to other models alias the
+/// standard ones or read as 0 and ignore writes.
+pub struct MockRegisters {
+	state: RefCell<State>,
+}
+
+impl MockRegisters {
+	/// Creates a new instance of `MockRegisters` in the reset state.
+	pub const fn new() -> Self {
+		Self {
+			state: RefCell::new(State {
+				rx: [0; FIFO_SIZE],
+				rx_errors: [0; FIFO_SIZE],
+				rx_len: 0,
+				tx: [0; TX_LOG_SIZE],
+				tx_len: 0,
+				overrun: false,
+				thre_pending: false,
+				ier: 0,
+				fcr: 0,
+				lcr: 0,
+				mcr: 0,
+				msr: 0,
+				scr: 0,
+				dll: 0,
+				dlm: 0,
+			}),
+		}
+	}
+
+	/// Injects `bytes` in the receiver, and returns the number of bytes stored before the
+	/// receiver FIFO was full.
+	pub fn receive(&self, bytes: &[u8]) -> usize {
+		let mut state = self.state.borrow_mut();
+		bytes.iter().filter(|&&c| state.push_rx(c, 0)).count()
+	}
+
+	/// Injects `c` in the receiver with `error`, and returns whether it was stored before the
+	/// receiver FIFO was full. An overrun error is reported without any other.
+	pub fn receive_with_error(&self, c: u8, error: RxError) -> bool {
+		let mut state = self.state.borrow_mut();
+		let bits = match error {
+			RxError::Overrun => {
+				state.overrun = true;
+				0
+			}
+			RxError::Parity => 0x04,
+			RxError::Framing => 0x08,
+			RxError::Break => 0x10,
+		};
+		state.push_rx(c, bits)
+	}
+
+	/// Moves the bytes transmitted since the last call to `buf`, and returns the number of
+	/// bytes moved. The bytes transmitted once the log is full are lost.
+	pub fn take_transmitted(&self, buf: &mut [u8]) -> usize {
+		let mut state = self.state.borrow_mut();
+		let len = state.tx_len.min(buf.len());
+		buf[..len].copy_from_slice(&state.tx[..len]);
+		state.tx.copy_within(len.., 0);
+		state.tx_len -= len;
+		len
+	}
+
+	/// Sets the modem status register, whose delta bits the driver clears by reading it.
+	pub fn set_modem_status(&self, msr: u8) {
+		self.state.borrow_mut().msr = msr;
+	}
+
+	/// Returns the divisor latch
+	pub fn divisor(&self) -> u16 {
+		let state = self.state.borrow();
+		state.dll as u16 | (state.dlm as u16) << 8
+	}
+}
+
+impl Default for MockRegisters {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl State {
+	/// Returns the depth of the receiver FIFO, 1 if the FIFOs are disabled.
+	fn rx_depth(&self) -> usize {
+		if self.fcr & 0x01 == 0 {
+			1
+		} else {
+			FIFO_SIZE
+		}
+	}
+
+	fn push_rx(&mut self, c: u8, errors: u8) -> bool {
+		if self.rx_len == self.rx_depth() {
+			self.overrun = true;
+			return false;
+		}
+		self.rx[self.rx_len] = c;
+		self.rx_errors[self.rx_len] = errors;
+		self.rx_len += 1;
+		true
+	}
+
+	fn pop_rx(&mut self) -> u8 {
+		if self.rx_len == 0 {
+			return 0;
+		}
+		let c = self.rx[0];
+		self.rx.copy_within(1..self.rx_len, 0);
+		self.rx_errors.copy_within(1..self.rx_len, 0);
+		self.rx_len -= 1;
+		c
+	}
+
+	fn transmit(&mut self, c: u8) {
+		if self.mcr & 0x10 != 0 {
+			self.push_rx(c, 0);
+		} else if self.tx_len < TX_LOG_SIZE {
+			self.tx[self.tx_len] = c;
+			self.tx_len += 1;
+		}
+		self.thre_pending = true;
+	}
+
+	fn iir(&mut self) -> u8 {
+		let fifo = if self.fcr & 0x01 == 0 { 0x00 } else { 0xC0 };
+		let cause = if self.ier & 0x04 != 0 && self.overrun {
+			0x06
+		} else if self.ier & 0x01 != 0 && self.rx_len != 0 {
+			0x04
+		} else if self.ier & 0x02 != 0 && self.thre_pending {
+			self.thre_pending = false;
+			0x02
+		} else if self.ier & 0x08 != 0 && self.msr & 0x0F != 0 {
+			0x00
+		} else {
+			0x01
+		};
+		fifo | cause
+	}
+
+	fn lsr(&mut self) -> u8 {
+		let mut lsr = 0x60 | (self.rx_len != 0) as u8 | (self.overrun as u8) << 1;
+		if self.rx_len != 0 {
+			lsr |= self.rx_errors[0];
+			self.rx_errors[0] = 0;
+		}
+		self.overrun = false;
+		lsr
+	}
+
+	fn write_fcr(&mut self, value: u8) {
+		if value & 0x01 != self.fcr & 0x01 || value & 0x02 != 0 {
+			self.rx_len = 0;
+		}
+		self.fcr = value & !0x06;
+	}
+
+	fn write_ier(&mut self, value: u8) {
+		// Enabling the interrupt while the transmitter holding register is empty raises it
+		if value & !self.ier & 0x02 != 0 {
+			self.thre_pending = true;
+		}
+		self.ier = value & 0x0F;
+	}
+}
+
+impl RegisterAccess for MockRegisters {
+	fn read(&self, reg: Register) -> u8 {
+		let mut state = self.state.borrow_mut();
+		let dlab = state.lcr & 0x80 != 0;
+		match (reg.offset(), dlab) {
+			(0, true) => state.dll,
+			(1, true) => state.dlm,
+			(0, false) => state.pop_rx(),
+			(1, false) => state.ier,
+			(2, _) => state.iir(),
+			(3, _) => state.lcr,
+			(4, _) => state.mcr,
+			(5, _) => state.lsr(),
+			(6, _) => {
+				let msr = state.msr;
+				state.msr &= 0xF0;
+				msr
+			}
+			(7, _) => state.scr,
+			_ => 0,
+		}
+	}
+
+	fn write(&self, reg: Register, value: u8) {
+		let mut state = self.state.borrow_mut();
+		let dlab = state.lcr & 0x80 != 0;
+		match (reg.offset(), dlab) {
+			(0, true) => state.dll = value,
+			(1, true) => state.dlm = value,
+			(0, false) => state.transmit(value),
+			(1, false) => state.write_ier(value),
+			(2, _) => state.write_fcr(value),
+			(3, _) => state.lcr = value,
+			(4, _) => state.mcr = value & 0x1F,
+			(7, _) => state.scr = value,
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{BufferedUart, Config, InterruptEnable, Newline, Uart};
+	use core::fmt::Write;
+
+	const XON: u8 = 0x11;
+	const XOFF: u8 = 0x13;
+
+	#[test]
+	fn init_divisor() {
+		let mock = MockRegisters::new();
+		let uart = Uart::with_access(&mock);
+		uart.init_with(&Config::new().divisor(0x1234));
+		assert_eq!(mock.divisor(), 0x1234);
+		assert_eq!(uart.current_config().divisor, 0x1234);
+	}
+
+	#[test]
+	fn pc_compatible_irq_output() {
+		let mock = MockRegisters::new();
+		let mut uart = Uart::with_access(&mock);
+		uart.set_pc_compatible(true);
+		uart.set_ier(InterruptEnable::default());
+		assert!(!uart.mcr().out2);
+		uart.set_ier(InterruptEnable {
+			received_data: true,
+			..InterruptEnable::default()
+		});
+		assert!(uart.mcr().out2);
+	}
+
+	#[test]
+	fn init_from_hardware() {
+		let mock = MockRegisters::new();
+		Uart::with_access(&mock).init_with(&Config::new().divisor(3).fifo(false));
+		let uart = Uart::with_access(&mock);
+		let ier = InterruptEnable {
+			received_data: true,
+			..InterruptEnable::default()
+		};
+		let config = uart.init_from_hardware(ier);
+		assert_eq!(config.divisor, 3);
+		assert!(config.fifo);
+		assert_eq!(mock.divisor(), 3);
+		assert_eq!(uart.ier(), ier);
+	}
+
+	#[test]
+	fn init_from_hardware_pc_compatible() {
+		let mock = MockRegisters::new();
+		let mut uart = Uart::with_access(&mock);
+		uart.set_pc_compatible(true);
+		uart.init_from_hardware(InterruptEnable {
+			received_data: true,
+			..InterruptEnable::default()
+		});
+		assert!(uart.mcr().out2);
+	}
+
+	#[test]
+	fn get_with_error() {
+		let mock = MockRegisters::new();
+		let uart = Uart::with_access(&mock);
+		uart.init_with(&Config::new().fifo(false));
+		assert_eq!(uart.get_with_error(), Ok(None));
+		assert_eq!(mock.receive(b"ab"), 1);
+		assert_eq!(uart.get_with_error(), Err(RxError::Overrun));
+		assert_eq!(uart.get_with_error(), Ok(Some(b'a')));
+		assert_eq!(uart.get_with_error(), Ok(None));
+	}
+
+	#[test]
+	fn get_clears_errors() {
+		let mock = MockRegisters::new();
+		let uart = Uart::with_access(&mock);
+		uart.init_with(&Config::new());
+		assert!(mock.receive_with_error(b'a', RxError::Parity));
+		assert_eq!(mock.receive(b"bc"), 2);
+		uart.flush();
+		assert_eq!(uart.get(), Some(b'a'));
+		assert_eq!(uart.get_with_error(), Ok(Some(b'b')));
+		assert_eq!(uart.get_with_error(), Ok(Some(b'c')));
+	}
+
+	#[test]
+	fn split_keeps_errors() {
+		let mock = MockRegisters::new();
+		let uart = Uart::with_access(&mock);
+		uart.init_with(&Config::new().fifo(false));
+		let (tx, rx) = uart.split();
+		assert_eq!(mock.receive(b"ab"), 1);
+		tx.flush();
+		assert_eq!(rx.get_with_error(), Err(RxError::Overrun));
+		assert_eq!(rx.get_with_error(), Ok(Some(b'a')));
+	}
+
+	#[test]
+	fn write_hook() {
+		fn continues(written: usize) -> bool {
+			written < 3
+		}
+		let mock = MockRegisters::new();
+		let mut uart = Uart::with_access(&mock);
+		uart.init_with(&Config::new());
+		uart.set_write_hook(Some(&continues));
+		assert_eq!(uart.write_bytes(b"hello"), 3);
+		let mut buf = [0; 8];
+		assert_eq!(mock.take_transmitted(&mut buf), 3);
+		assert_eq!(&buf[..3], b"hel");
+	}
+
+	#[test]
+	fn buffered_round_trip() {
+		let mock = MockRegisters::new();
+		let uart = BufferedUart::<_, 16, 16>::new(Uart::with_access(&mock));
+		uart.uart().init_with(&Config::new());
+		uart.enable();
+
+		assert_eq!(mock.receive(b"hello"), 5);
+		assert!(uart.handle_interrupt().rx_ready);
+		let mut buf = [0; 8];
+		assert_eq!(uart.read(&mut buf), 5);
+		assert_eq!(&buf[..5], b"hello");
+
+		assert_eq!(uart.write(b"world"), 5);
+		assert!(uart.handle_interrupt().tx_empty);
+		assert_eq!(mock.take_transmitted(&mut buf), 5);
+		assert_eq!(&buf[..5], b"world");
+		assert_eq!(uart.bytes_pending(), 0);
+	}
+
+	#[test]
+	fn buffered_newline() {
+		let mock = MockRegisters::new();
+		let mut uart = BufferedUart::<_, 16, 16>::new(Uart::with_access(&mock));
+		uart.uart_mut().set_newline(Newline {
+			lf_to_crlf: true,
+			..Newline::NONE
+		});
+		uart.uart().init_with(&Config::new());
+		uart.enable();
+		write!(uart, "a\nb").unwrap();
+		uart.handle_interrupt();
+		let mut buf = [0; 8];
+		assert_eq!(mock.take_transmitted(&mut buf), 4);
+		assert_eq!(&buf[..4], b"a\r\nb");
+	}
+
+	#[test]
+	fn buffered_hardware_flow_control() {
+		let mock = MockRegisters::new();
+		let mut uart = BufferedUart::<_, 16, 16>::new(Uart::with_access(&mock));
+		uart.uart().init_with(&Config::new());
+		uart.enable();
+		uart.set_hardware_flow_control(true);
+		assert!(uart.uart().ier().modem_status);
+		assert!(uart.uart().mcr().rts);
+
+		// Deasserted once the receive buffer is three quarters full
+		assert_eq!(mock.receive(&[0; 12]), 12);
+		uart.handle_interrupt();
+		assert!(!uart.uart().mcr().rts);
+
+		// Asserted by the interrupt handler once the receive buffer is drained to a quarter
+		let mut buf = [0; 8];
+		assert_eq!(uart.read(&mut buf), 8);
+		assert!(!uart.uart().mcr().rts);
+		uart.handle_interrupt();
+		assert!(uart.uart().mcr().rts);
+	}
+
+	#[test]
+	fn buffered_flow_control_disabled_while_paused() {
+		let mock = MockRegisters::new();
+		let mut uart = BufferedUart::<_, 16, 16>::new(Uart::with_access(&mock));
+		uart.uart().init_with(&Config::new());
+		uart.enable();
+		uart.set_software_flow_control(true);
+		uart.set_hardware_flow_control(true);
+		assert_eq!(mock.receive(&[0; 12]), 12);
+		uart.handle_interrupt();
+		let mut buf = [0; 4];
+		assert_eq!(mock.take_transmitted(&mut buf), 1);
+		assert_eq!(buf[0], XOFF);
+		assert!(!uart.uart().mcr().rts);
+
+		// The remote is resumed although the receive buffer is not drained
+		uart.set_hardware_flow_control(false);
+		assert!(uart.uart().mcr().rts);
+		uart.handle_interrupt();
+		assert_eq!(mock.take_transmitted(&mut buf), 1);
+		assert_eq!(buf[0], XON);
+
+		assert_eq!(mock.receive(&[0]), 1);
+		uart.handle_interrupt();
+		assert_eq!(mock.take_transmitted(&mut buf), 1);
+		assert_eq!(buf[0], XOFF);
+		uart.set_software_flow_control(false);
+		uart.handle_interrupt();
+		assert_eq!(mock.take_transmitted(&mut buf), 1);
+		assert_eq!(buf[0], XON);
+	}
+}
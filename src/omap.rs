@@ -0,0 +1,32 @@
+//! Mode selection of the TI OMAP and AM335x UARTs.
+
+use crate::{Config, Register, RegisterAccess, Uart};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Operating mode selected in the mode definition register 1 of the TI peripherals
+pub enum OmapMode {
+	/// UART with a 16x oversampling
+	UART16X = 0,
+	/// UART with a 16x oversampling and automatic baud rate detection
+	AUTOBAUD = 2,
+	/// UART with a 13x oversampling
+	UART13X = 3,
+	/// Disabled, the reset mode
+	DISABLE = 7,
+}
+
+impl<R: RegisterAccess> Uart<R> {
+	/// Selects the operating mode in the mode definition register 1 of a TI peripheral.
+	pub fn set_omap_mode(&self, mode: OmapMode) {
+		let mdr1 = self.regs.read(Register::MDR1);
+		self.regs.write(Register::MDR1, (mdr1 & !0x07) | mode as u8);
+	}
+
+	/// Initializes a TI peripheral with the given configuration and operating mode. The
+	/// peripheral is disabled while being configured, as required to change the divisor latch.
+	pub fn init_omap(&self, config: &Config, mode: OmapMode) {
+		self.set_omap_mode(OmapMode::DISABLE);
+		self.init_with(config);
+		self.set_omap_mode(mode);
+	}
+}
@@ -0,0 +1,88 @@
+//! Enhanced mode of the Oxford 16C950.
+
+use crate::{Register, RegisterAccess, Uart};
+use core::sync::atomic::Ordering;
+
+/// Index of the additional control register
+const ACR: u8 = 0x00;
+/// Index of the transmitter trigger level register
+const TTL: u8 = 0x04;
+/// Index of the receiver trigger level register
+const RTL: u8 = 0x05;
+/// Index of the first identification register
+const ID1: u8 = 0x08;
+/// Content of the identification registers of the 16C950
+const OX16C950_ID: [u8; 3] = [0x16, 0xC9, 0x50];
+
+impl<R: RegisterAccess> Uart<R> {
+	/// Returns whether the peripheral, whose FIFOs are enabled, is a 16C950. The enhanced
+	/// features register is checked to be readable before the identification registers are
+	/// read. `lcr` is the line control register, which is restored, and must have DLAB
+	/// cleared.
+	pub(crate) fn has_ox16c950_id(&self, lcr: u8) -> bool {
+		self.write_lcr(0xBF);
+		// Without enhanced features register, the interrupt identification register is read,
+		// whose FIFO bits are set
+		let efr = self.regs.read(Register::EFR);
+		if efr & !0x10 != 0 {
+			self.write_lcr(lcr);
+			return false;
+		}
+		self.regs.write(Register::EFR, efr | 0x10);
+		self.write_lcr(lcr);
+		let mut id = [0; 3];
+		id.iter_mut()
+			.zip(ID1..)
+			.for_each(|(byte, index)| *byte = self.icr_read(index));
+		self.write_lcr(0xBF);
+		self.regs.write(Register::EFR, efr);
+		self.write_lcr(lcr);
+		id == OX16C950_ID
+	}
+
+	/// Returns the value read in the indexed control register `index`. The additional control
+	/// register is restored afterwards.
+	fn icr_read(&self, index: u8) -> u8 {
+		let acr = self.acr.load(Ordering::Relaxed);
+		self.icr_write(ACR, acr | 0x40);
+		self.regs.write(Register::SCR, index);
+		let value = self.regs.read(Register::ICR);
+		self.icr_write(ACR, acr);
+		value
+	}
+
+	fn icr_write(&self, index: u8, value: u8) {
+		self.regs.write(Register::SCR, index);
+		self.regs.write(Register::ICR, value);
+	}
+
+	/// Enables or disables the enhanced mode of the 16C950, in which the FIFOs are 128 bytes
+	/// deep, and updates [`Capabilities::fifo_depth`](crate::Capabilities::fifo_depth)
+	/// accordingly. Requires [`Capabilities::fifo128`](crate::Capabilities::fifo128).
+	pub fn set_enhanced_mode(&mut self, enabled: bool) {
+		if !self.capabilities.fifo128 {
+			return;
+		}
+		let lcr = self.regs.read(Register::LCR);
+		self.write_lcr(0xBF);
+		let efr = self.regs.read(Register::EFR);
+		self.regs
+			.write(Register::EFR, (efr & !0x10) | ((enabled as u8) << 4));
+		self.write_lcr(lcr);
+		self.capabilities.fifo_depth = if enabled { 128 } else { 0 };
+	}
+
+	/// Sets the receiver and transmitter FIFO trigger levels of the 16C950 in enhanced mode, in
+	/// bytes from 1 to 127, overriding the trigger level of the FIFO control register. The other
+	/// bits of the additional control register are cleared. Requires
+	/// [`Capabilities::fifo128`](crate::Capabilities::fifo128). DLAB must be cleared.
+	pub fn set_trigger_levels(&self, rx: u8, tx: u8) {
+		if !self.capabilities.fifo128 {
+			return;
+		}
+		self.icr_write(RTL, rx);
+		self.icr_write(TTL, tx);
+		self.acr.store(0x20, Ordering::Relaxed);
+		self.icr_write(ACR, 0x20);
+	}
+}
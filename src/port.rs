@@ -0,0 +1,104 @@
+//! x86 I/O port register access.
+
+use crate::{Register, RegisterAccess, Uart};
+use core::arch::asm;
+
+#[derive(Debug, PartialEq, Eq)]
+/// Register access through the x86 I/O ports
+pub struct PortIo {
+	/// First I/O port of the peripheral
+	port: u16,
+}
+
+impl PortIo {
+	/// First I/O port of COM1
+	pub const COM1: u16 = 0x3F8;
+	/// First I/O port of COM2
+	pub const COM2: u16 = 0x2F8;
+	/// First I/O port of COM3
+	pub const COM3: u16 = 0x3E8;
+	/// First I/O port of COM4
+	pub const COM4: u16 = 0x2E8;
+	/// First I/O ports of COM1 to COM4
+	pub const COM: [u16; 4] = [Self::COM1, Self::COM2, Self::COM3, Self::COM4];
+
+	/// Creates a new instance of `PortIo` with the registers starting at `port`, e.g.
+	/// [`PortIo::COM1`].
+	///
+	/// # Safety
+	///
+	/// The 8 I/O ports starting at `port` must be the registers of a NS16550A compatible
+	/// peripheral, and must not be accessed concurrently by other means in a way that conflicts
+	/// with the instance.
+	pub const unsafe fn new(port: u16) -> Self {
+		Self { port }
+	}
+
+	/// Returns the first I/O port
+	pub const fn port(&self) -> u16 {
+		self.port
+	}
+}
+
+impl RegisterAccess for PortIo {
+	fn read(&self, reg: Register) -> u8 {
+		let port = self.port + reg.offset() as u16;
+		let value: u8;
+		unsafe {
+			asm!("in al, dx", out("al") value, in("dx") port, options(nomem, nostack, preserves_flags));
+		}
+		value
+	}
+
+	fn write(&self, reg: Register, value: u8) {
+		let port = self.port + reg.offset() as u16;
+		unsafe {
+			asm!("out dx, al", in("dx") port, in("al") value, options(nomem, nostack, preserves_flags));
+		}
+	}
+}
+
+#[derive(Debug)]
+/// Standard COM ports found by [`probe`](Self::probe)
+pub struct SerialPorts {
+	ports: [Option<Uart<PortIo>>; 4],
+}
+
+impl SerialPorts {
+	/// Probes COM1 to COM4. A port is found if its scratch register holds the values written,
+	/// so the 8250, which does not have a scratch register, is not found. The ports found are
+	/// set as PC compatible (see [`Uart::set_pc_compatible`]).
+	///
+	/// # Safety
+	///
+	/// See [`PortIo::new`], for each of COM1 to COM4: the registers of all of them are written,
+	/// so the caller must own the ports, even those which are not found.
+	pub unsafe fn probe() -> Self {
+		Self {
+			ports: PortIo::COM.map(|port| {
+				let mut uart = Uart::with_port(port);
+				uart.set_pc_compatible(true);
+				if uart.has_scratch() {
+					Some(uart)
+				} else {
+					None
+				}
+			}),
+		}
+	}
+
+	/// Returns the port at `index`, 0 for COM1, if it was found.
+	pub fn get(&self, index: usize) -> Option<&Uart<PortIo>> {
+		self.ports.get(index)?.as_ref()
+	}
+
+	/// Removes the port at `index`, 0 for COM1, if it was found, and returns it.
+	pub fn take(&mut self, index: usize) -> Option<Uart<PortIo>> {
+		self.ports.get_mut(index)?.take()
+	}
+
+	/// Returns an iterator over the ports found and not taken.
+	pub fn iter(&self) -> impl Iterator<Item = &Uart<PortIo>> {
+		self.ports.iter().flatten()
+	}
+}
@@ -0,0 +1,430 @@
+//! Register access.
+//!
+//! All the accesses to the registers of the peripheral go through [`RegisterAccess`], so that
+//! the unsafe code is limited to its implementations. The memory-mapped implementation computes
+//! the address of each register from its offset and the register shift, instead of using a
+//! `#[repr(C)]` register block, as the stride and the access width are only known at runtime.
+//! Each register, including the two halves of the divisor latch, is accessed separately.
+//!
+//! The addresses are converted to pointers with exposed provenance, as the registers are not
+//! part of any allocation known to Rust, so that the crate remains compatible with strict
+//! provenance checks of the surrounding code.
+
+use core::ptr::with_exposed_provenance_mut;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Register of the peripheral
+pub enum Register {
+	/// Receiver buffer register (read, DLAB cleared)
+	RBR,
+	/// Transmitter holding register (write, DLAB cleared)
+	THR,
+	/// Interrupt enable register (DLAB cleared)
+	IER,
+	/// Interrupt identification register (read)
+	IIR,
+	/// FIFO control register (write)
+	FCR,
+	/// Line control register
+	LCR,
+	/// Modem control register
+	MCR,
+	/// Line status register
+	LSR,
+	/// Modem status register
+	MSR,
+	/// Scratch register
+	SCR,
+	/// Enhanced features register (16C950, line control register set to `0xBF`)
+	EFR,
+	/// Indexed control register (16C950), selected by writing its index in the scratch register
+	ICR,
+	/// Divisor latch LSB (DLAB set)
+	DLL,
+	/// Divisor latch MSB (DLAB set)
+	DLM,
+	/// Divisor latch fraction (DesignWare)
+	DLF,
+	/// UART status register (DesignWare, read)
+	USR,
+	/// Component parameter register (DesignWare, read, 32 bits)
+	CPR,
+	/// Mode definition register 1 (TI)
+	MDR1,
+}
+
+impl Register {
+	/// Returns the offset of the register, in number of registers from the base address.
+	pub const fn offset(self) -> usize {
+		match self {
+			Self::RBR | Self::THR | Self::DLL => 0,
+			Self::IER | Self::DLM => 1,
+			Self::IIR | Self::FCR | Self::EFR => 2,
+			Self::LCR => 3,
+			Self::MCR => 4,
+			Self::LSR | Self::ICR => 5,
+			Self::MSR => 6,
+			Self::SCR => 7,
+			Self::MDR1 => 8,
+			Self::USR => 0x1F,
+			Self::DLF => 0x30,
+			Self::CPR => 0x3D,
+		}
+	}
+}
+
+/// Access to the registers of the peripheral
+pub trait RegisterAccess {
+	/// Returns the value read in `reg`.
+	fn read(&self, reg: Register) -> u8;
+
+	/// Writes `value` in `reg`.
+	fn write(&self, reg: Register, value: u8);
+
+	/// Returns the 32 bits value read in `reg`, for the registers wider than 8 bits. Only the
+	/// low byte is read by default.
+	fn read_u32(&self, reg: Register) -> u32 {
+		self.read(reg) as u32
+	}
+}
+
+impl<R: RegisterAccess + ?Sized> RegisterAccess for &R {
+	fn read(&self, reg: Register) -> u8 {
+		(**self).read(reg)
+	}
+
+	fn write(&self, reg: Register, value: u8) {
+		(**self).write(reg, value)
+	}
+
+	fn read_u32(&self, reg: Register) -> u32 {
+		(**self).read_u32(reg)
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Width of the register accesses, as described by the `reg-io-width` device tree property
+pub enum IoWidth {
+	U8 = 1,
+	U16 = 2,
+	U32 = 4,
+}
+
+impl IoWidth {
+	/// Returns the access width of `bytes` bytes, as given by the `reg-io-width` device tree
+	/// property, or `None` if it is not 1, 2 or 4.
+	pub const fn from_bytes(bytes: u32) -> Option<Self> {
+		match bytes {
+			1 => Some(Self::U8),
+			2 => Some(Self::U16),
+			4 => Some(Self::U32),
+			_ => None,
+		}
+	}
+}
+
+/// Returns a pointer to the register at `address`.
+fn ptr<T>(address: usize) -> *mut T {
+	with_exposed_provenance_mut(address)
+}
+
+/// Orders the memory writes before the following register write, e.g. so that a buffer is
+/// written before a DMA transfer is started.
+fn write_barrier() {
+	#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+	unsafe {
+		core::arch::asm!("fence w, o", options(nostack, preserves_flags));
+	}
+	#[cfg(target_arch = "aarch64")]
+	unsafe {
+		core::arch::asm!("dmb oshst", options(nostack, preserves_flags));
+	}
+	#[cfg(all(
+		target_arch = "arm",
+		target_feature = "v7",
+		not(target_feature = "mclass")
+	))]
+	unsafe {
+		core::arch::asm!("dmb oshst", options(nostack, preserves_flags));
+	}
+	#[cfg(all(target_arch = "arm", target_feature = "mclass"))]
+	unsafe {
+		core::arch::asm!("dmb", options(nostack, preserves_flags));
+	}
+	#[cfg(not(any(
+		target_arch = "riscv32",
+		target_arch = "riscv64",
+		target_arch = "aarch64",
+		all(
+			target_arch = "arm",
+			any(target_feature = "v7", target_feature = "mclass")
+		)
+	)))]
+	core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+}
+
+/// Orders the previous register read before the following memory reads, e.g. so that a buffer
+/// is read after a DMA transfer is reported as complete.
+fn read_barrier() {
+	#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+	unsafe {
+		core::arch::asm!("fence i, r", options(nostack, preserves_flags));
+	}
+	#[cfg(target_arch = "aarch64")]
+	unsafe {
+		core::arch::asm!("dmb oshld", options(nostack, preserves_flags));
+	}
+	#[cfg(all(
+		target_arch = "arm",
+		target_feature = "v7",
+		not(target_feature = "mclass")
+	))]
+	unsafe {
+		core::arch::asm!("dmb osh", options(nostack, preserves_flags));
+	}
+	#[cfg(all(target_arch = "arm", target_feature = "mclass"))]
+	unsafe {
+		core::arch::asm!("dmb", options(nostack, preserves_flags));
+	}
+	#[cfg(not(any(
+		target_arch = "riscv32",
+		target_arch = "riscv64",
+		target_arch = "aarch64",
+		all(
+			target_arch = "arm",
+			any(target_feature = "v7", target_feature = "mclass")
+		)
+	)))]
+	core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+}
+
+#[derive(Debug, PartialEq, Eq)]
+/// Memory-mapped register access
+pub struct Mmio {
+	/// Base address of the peripheral
+	base_address: usize,
+	/// Number of bits the register offsets are shifted by
+	reg_shift: u8,
+	/// Width of the register accesses
+	reg_io_width: IoWidth,
+	/// Offset of the significant byte within each register, for byte wide accesses
+	byte_lane: u8,
+	/// Whether the register accesses are ordered with the memory accesses
+	barriers: bool,
+}
+
+impl Mmio {
+	/// Creates a new instance of `Mmio` with the given base address, with the registers spaced
+	/// by `1 << reg_shift` bytes and accessed with `reg_io_width` wide reads and writes. Only
+	/// the low byte is significant: reads are truncated and writes are zero-extended.
+	///
+	/// # Safety
+	///
+	/// `base_address` must be the address of the registers of a NS16550A compatible peripheral
+	/// with the given layout, mapped and accessible with volatile accesses for as long as the
+	/// instance is used. Its registers must not be accessed concurrently by other means in a
+	/// way that conflicts with the instance.
+	///
+	/// # Panics
+	///
+	/// Panics if `reg_shift` is not less than the number of bits of an address.
+	pub const unsafe fn new(base_address: usize, reg_shift: u8, reg_io_width: IoWidth) -> Self {
+		assert!(
+			(reg_shift as u32) < usize::BITS,
+			"the register shift must be less than the number of bits of an address"
+		);
+		Self {
+			base_address,
+			reg_shift,
+			reg_io_width,
+			byte_lane: 0,
+			barriers: false,
+		}
+	}
+
+	/// Returns the instance with `base_address` as the base address, keeping the layout.
+	///
+	/// # Safety
+	///
+	/// See [`new`](Self::new).
+	pub const unsafe fn with_base_address(self, base_address: usize) -> Self {
+		Self {
+			base_address,
+			..self
+		}
+	}
+
+	/// Moves the registers to `base_address`, keeping the layout.
+	///
+	/// # Safety
+	///
+	/// See [`new`](Self::new).
+	pub(crate) unsafe fn set_base_address(&mut self, base_address: usize) {
+		self.base_address = base_address;
+	}
+
+	/// Sets whether the register accesses are ordered with the memory accesses, see
+	/// [`with_barriers`](Self::with_barriers).
+	pub(crate) fn set_barriers(&mut self, barriers: bool) {
+		self.barriers = barriers;
+	}
+
+	/// Returns the instance with barriers ordering the register accesses with the memory
+	/// accesses if `barriers` is set, for weakly ordered processors. The memory writes are
+	/// ordered before each register write, and each register read before the following memory
+	/// reads, as needed e.g. when a register write starts a DMA transfer or enables an interrupt
+	/// whose handler reads memory written before.
+	///
+	/// The barriers are `fence w, o` and `fence i, r` on RISC-V, `dmb oshst` and `dmb oshld`
+	/// on AArch64, `dmb oshst` and `dmb osh` on 32-bit ARMv7-A and ARMv7-R, and `dmb` on
+	/// M-profile ARM. As the compiler only reports the ARM architecture versions to `cfg` on
+	/// nightly, 32-bit ARM otherwise falls back, like the other architectures, to a
+	/// sequentially consistent fence, which only orders the accesses within the inner
+	/// shareable domain.
+	pub const fn with_barriers(self, barriers: bool) -> Self {
+		Self { barriers, ..self }
+	}
+
+	/// Returns the instance with the registers accessed `byte_lane` bytes past their address,
+	/// e.g. 3 on big-endian platforms whose registers are 32 bits wide but accessed with byte
+	/// wide reads and writes, so that the significant byte is the last one. The byte lane only
+	/// applies to the byte wide accesses: the accesses of [`IoWidth::U16`] and [`IoWidth::U32`]
+	/// wide instances, and those of [`read_u32`](RegisterAccess::read_u32), start at the address
+	/// of the register.
+	///
+	/// # Safety
+	///
+	/// If the access width is [`IoWidth::U8`], the registers must be accessible as described by
+	/// [`new`](Self::new) at `byte_lane` bytes past their address, which must be less than the
+	/// spacing of the registers.
+	pub const unsafe fn with_byte_lane(self, byte_lane: u8) -> Self {
+		Self { byte_lane, ..self }
+	}
+
+	/// Returns the base address
+	pub const fn base_address(&self) -> usize {
+		self.base_address
+	}
+
+	/// Returns the number of bits the register offsets are shifted by
+	pub const fn reg_shift(&self) -> u8 {
+		self.reg_shift
+	}
+
+	/// Returns the width of the register accesses
+	pub const fn reg_io_width(&self) -> IoWidth {
+		self.reg_io_width
+	}
+
+	/// Returns the offset of the significant byte within each register
+	pub const fn byte_lane(&self) -> u8 {
+		self.byte_lane
+	}
+
+	/// Returns whether the register accesses are ordered with the memory accesses
+	pub const fn barriers(&self) -> bool {
+		self.barriers
+	}
+
+	/// Returns the address of `reg`, including the byte lane for byte wide accesses.
+	pub fn register_address(&self, reg: Register) -> usize {
+		self.address(reg)
+	}
+
+	fn address(&self, reg: Register) -> usize {
+		match self.reg_io_width {
+			IoWidth::U8 => self.register_start(reg) + self.byte_lane as usize,
+			IoWidth::U16 | IoWidth::U32 => self.register_start(reg),
+		}
+	}
+
+	fn register_start(&self, reg: Register) -> usize {
+		self.base_address + (reg.offset() << self.reg_shift)
+	}
+}
+
+impl RegisterAccess for Mmio {
+	fn read(&self, reg: Register) -> u8 {
+		let address = self.address(reg);
+		let value = unsafe {
+			match self.reg_io_width {
+				IoWidth::U8 => ptr::<u8>(address).read_volatile(),
+				IoWidth::U16 => ptr::<u16>(address).read_volatile() as u8,
+				IoWidth::U32 => ptr::<u32>(address).read_volatile() as u8,
+			}
+		};
+		if self.barriers {
+			read_barrier();
+		}
+		value
+	}
+
+	/// Reads the 32 bits with a single access if the access width is 32 bits, otherwise with
+	/// several narrower little-endian accesses.
+	fn read_u32(&self, reg: Register) -> u32 {
+		let address = self.register_start(reg);
+		let value = unsafe {
+			match self.reg_io_width {
+				IoWidth::U8 => (0..4).fold(0, |value, i| {
+					value | (ptr::<u8>(address).add(i).read_volatile() as u32) << (8 * i)
+				}),
+				IoWidth::U16 => {
+					let low = ptr::<u16>(address).read_volatile() as u32;
+					low | (ptr::<u16>(address).add(1).read_volatile() as u32) << 16
+				}
+				IoWidth::U32 => ptr::<u32>(address).read_volatile(),
+			}
+		};
+		if self.barriers {
+			read_barrier();
+		}
+		value
+	}
+
+	fn write(&self, reg: Register, value: u8) {
+		let address = self.address(reg);
+		if self.barriers {
+			write_barrier();
+		}
+		unsafe {
+			match self.reg_io_width {
+				IoWidth::U8 => ptr::<u8>(address).write_volatile(value),
+				IoWidth::U16 => ptr::<u16>(address).write_volatile(value as u16),
+				IoWidth::U32 => ptr::<u32>(address).write_volatile(value as u32),
+			}
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Memory-mapped register access with a base address and a register shift known at compile
+/// time, accessed with byte wide reads and writes
+///
+/// Unlike [`Mmio`], this type is zero-sized and the addresses of the registers are constants.
+pub struct MmioAt<const BASE: usize, const REG_SHIFT: u8 = 0>;
+
+impl<const BASE: usize, const REG_SHIFT: u8> MmioAt<BASE, REG_SHIFT> {
+	/// Creates a new instance of `MmioAt`.
+	///
+	/// # Safety
+	///
+	/// See [`Mmio::new`], with `BASE` as the base address, `REG_SHIFT` as the register shift and
+	/// [`IoWidth::U8`] as the access width.
+	pub const unsafe fn new() -> Self {
+		Self
+	}
+
+	const fn address(reg: Register) -> usize {
+		BASE + (reg.offset() << REG_SHIFT)
+	}
+}
+
+impl<const BASE: usize, const REG_SHIFT: u8> RegisterAccess for MmioAt<BASE, REG_SHIFT> {
+	fn read(&self, reg: Register) -> u8 {
+		unsafe { ptr::<u8>(Self::address(reg)).read_volatile() }
+	}
+
+	fn write(&self, reg: Register, value: u8) {
+		unsafe { ptr::<u8>(Self::address(reg)).write_volatile(value) }
+	}
+}
@@ -0,0 +1,63 @@
+//! Lock-free single-producer single-consumer ring buffer.
+
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// Ring buffer of `N` slots shared between one producer and one consumer, e.g. an interrupt
+/// handler and the main program. One slot is always left empty, so it holds at most `N - 1`
+/// bytes.
+///
+/// The slots are atomic so that concurrent accesses are never undefined behavior, but bytes
+/// may be duplicated or lost if there is more than one producer or consumer.
+pub(crate) struct RingBuffer<const N: usize> {
+	buffer: [AtomicU8; N],
+	/// Index of the next slot to write
+	head: AtomicUsize,
+	/// Index of the next slot to read
+	tail: AtomicUsize,
+}
+
+impl<const N: usize> RingBuffer<N> {
+	pub(crate) const fn new() -> Self {
+		assert!(N >= 2, "a ring buffer needs at least 2 slots");
+		Self {
+			buffer: [const { AtomicU8::new(0) }; N],
+			head: AtomicUsize::new(0),
+			tail: AtomicUsize::new(0),
+		}
+	}
+
+	/// Appends `c`, returning `false` if the buffer is full.
+	pub(crate) fn push(&self, c: u8) -> bool {
+		let head = self.head.load(Ordering::Relaxed);
+		let next = (head + 1) % N;
+		if next == self.tail.load(Ordering::Acquire) {
+			return false;
+		}
+		self.buffer[head].store(c, Ordering::Relaxed);
+		self.head.store(next, Ordering::Release);
+		true
+	}
+
+	/// Removes and returns the oldest byte, if any.
+	pub(crate) fn pop(&self) -> Option<u8> {
+		let tail = self.tail.load(Ordering::Relaxed);
+		if tail == self.head.load(Ordering::Acquire) {
+			return None;
+		}
+		let c = self.buffer[tail].load(Ordering::Relaxed);
+		self.tail.store((tail + 1) % N, Ordering::Release);
+		Some(c)
+	}
+
+	/// Returns the number of bytes in the buffer.
+	pub(crate) fn len(&self) -> usize {
+		let head = self.head.load(Ordering::Acquire);
+		let tail = self.tail.load(Ordering::Acquire);
+		(head + N - tail) % N
+	}
+
+	/// Returns whether the buffer is full.
+	pub(crate) fn is_full(&self) -> bool {
+		self.len() == N - 1
+	}
+}
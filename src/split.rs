@@ -0,0 +1,145 @@
+//! Transmitter and receiver halves.
+
+use crate::{RegisterAccess, RxError, TimedOut, Uart};
+use core::fmt::{Result, Write};
+
+#[derive(Debug)]
+/// Transmitter half of a UART peripheral, returned by [`Uart::split`]
+pub struct UartTx<'a, R: RegisterAccess> {
+	uart: &'a Uart<R>,
+}
+
+#[derive(Debug)]
+/// Receiver half of a UART peripheral, returned by [`Uart::split`]
+pub struct UartRx<'a, R: RegisterAccess> {
+	uart: &'a Uart<R>,
+}
+
+impl<R: RegisterAccess> Uart<R> {
+	/// Splits the peripheral into its transmitter and receiver halves, which can be used
+	/// independently. The receive errors cleared by the line status register reads of the
+	/// transmitter half are kept for [`UartRx::get_with_error`].
+	pub fn split(&self) -> (UartTx<'_, R>, UartRx<'_, R>) {
+		(UartTx { uart: self }, UartRx { uart: self })
+	}
+
+	/// Returns the transmitter half alone, which implements [`Write`] through a shared
+	/// reference to the peripheral, so that formatted output can be written from several places.
+	pub fn writer(&self) -> UartTx<'_, R> {
+		UartTx { uart: self }
+	}
+}
+
+impl<R: RegisterAccess> UartTx<'_, R> {
+	/// See [`Uart::put`].
+	pub fn put(&self, c: u8) -> Option<u8> {
+		self.uart.put(c)
+	}
+
+	/// See [`Uart::put_blocking`].
+	pub fn put_blocking(&self, c: u8) {
+		self.uart.put_blocking(c);
+	}
+
+	/// See [`Uart::write_bytes`].
+	pub fn write_bytes(&self, bytes: &[u8]) -> usize {
+		self.uart.write_bytes(bytes)
+	}
+
+	/// See [`Uart::write_nb`].
+	pub fn write_nb(&self, bytes: &[u8]) -> usize {
+		self.uart.write_nb(bytes)
+	}
+
+	/// See [`Uart::flush`].
+	pub fn flush(&self) {
+		self.uart.flush();
+	}
+
+	/// See [`Uart::try_flush`].
+	pub fn try_flush(&self) -> bool {
+		self.uart.try_flush()
+	}
+
+	/// See [`Uart::tx_ready`].
+	pub fn tx_ready(&self) -> bool {
+		self.uart.tx_ready()
+	}
+
+	/// See [`Uart::is_thr_empty`].
+	pub fn is_thr_empty(&self) -> bool {
+		self.uart.is_thr_empty()
+	}
+
+	/// See [`Uart::is_transmit_complete`].
+	pub fn is_transmit_complete(&self) -> bool {
+		self.uart.is_transmit_complete()
+	}
+
+	/// See [`Uart::clear_tx_fifo`].
+	pub fn clear_tx_fifo(&self) {
+		self.uart.clear_tx_fifo();
+	}
+}
+
+impl<R: RegisterAccess> Write for UartTx<'_, R> {
+	fn write_str(&mut self, s: &str) -> Result {
+		self.uart.write_text(s)
+	}
+}
+
+impl<R: RegisterAccess> UartRx<'_, R> {
+	/// See [`Uart::get`].
+	pub fn get(&self) -> Option<u8> {
+		self.uart.get()
+	}
+
+	/// See [`Uart::get_blocking`].
+	pub fn get_blocking(&self) -> u8 {
+		self.uart.get_blocking()
+	}
+
+	/// See [`Uart::incoming`].
+	pub fn incoming(&self) -> impl Iterator<Item = u8> + '_ {
+		self.uart.incoming()
+	}
+
+	/// See [`Uart::incoming_blocking`].
+	pub fn incoming_blocking(&self) -> impl Iterator<Item = u8> + '_ {
+		self.uart.incoming_blocking()
+	}
+
+	/// See [`Uart::rx_ready`].
+	pub fn rx_ready(&self) -> bool {
+		self.uart.rx_ready()
+	}
+
+	/// See [`Uart::read_available`].
+	pub fn read_available(&self, buf: &mut [u8]) -> usize {
+		self.uart.read_available(buf)
+	}
+
+	/// See [`Uart::get_timeout`].
+	pub fn get_timeout(&self, spins: usize) -> core::result::Result<u8, TimedOut> {
+		self.uart.get_timeout(spins)
+	}
+
+	/// See [`Uart::read_exact_timeout`].
+	pub fn read_exact_timeout(
+		&self,
+		buf: &mut [u8],
+		spins: usize,
+	) -> core::result::Result<(), TimedOut> {
+		self.uart.read_exact_timeout(buf, spins)
+	}
+
+	/// See [`Uart::get_with_error`].
+	pub fn get_with_error(&self) -> core::result::Result<Option<u8>, RxError> {
+		self.uart.get_with_error()
+	}
+
+	/// See [`Uart::clear_rx_fifo`].
+	pub fn clear_rx_fifo(&self) {
+		self.uart.clear_rx_fifo();
+	}
+}
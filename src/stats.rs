@@ -0,0 +1,104 @@
+//! Driver statistics.
+
+#[cfg(all(feature = "stats", target_has_atomic = "ptr"))]
+use crate::{RegisterAccess, Uart};
+#[cfg(all(feature = "stats", target_has_atomic = "ptr"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(all(feature = "stats", target_has_atomic = "ptr"))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Statistics collected by the driver, returned by [`Uart::stats`](crate::Uart::stats)
+pub struct Stats {
+	/// Number of characters written in the transmitter holding register
+	pub tx_bytes: usize,
+	/// Number of characters read in the receiver buffer register
+	pub rx_bytes: usize,
+	/// Number of overrun errors seen in the line status register
+	pub overrun_errors: usize,
+	/// Number of parity errors seen in the line status register
+	pub parity_errors: usize,
+	/// Number of framing errors seen in the line status register
+	pub framing_errors: usize,
+	/// Number of break conditions seen in the line status register
+	pub break_interrupts: usize,
+	/// Number of received characters discarded because a receive buffer was full
+	pub dropped_bytes: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Counter of [`Stats`]
+pub(crate) enum Counter {
+	TxBytes,
+	RxBytes,
+	OverrunErrors,
+	ParityErrors,
+	FramingErrors,
+	BreakInterrupts,
+	DroppedBytes,
+}
+
+/// Number of counters
+#[cfg(all(feature = "stats", target_has_atomic = "ptr"))]
+const COUNTERS: usize = 7;
+
+#[derive(Debug)]
+/// Counters of the statistics, which are zero-sized without the `stats` feature
+pub(crate) struct Counters {
+	#[cfg(all(feature = "stats", target_has_atomic = "ptr"))]
+	counts: [AtomicUsize; COUNTERS],
+}
+
+impl Counters {
+	pub(crate) const fn new() -> Self {
+		Self {
+			#[cfg(all(feature = "stats", target_has_atomic = "ptr"))]
+			counts: [const { AtomicUsize::new(0) }; COUNTERS],
+		}
+	}
+
+	/// Adds `n` to `counter`.
+	#[allow(unused_variables)]
+	pub(crate) fn add(&self, counter: Counter, n: usize) {
+		#[cfg(all(feature = "stats", target_has_atomic = "ptr"))]
+		self.counts[counter as usize].fetch_add(n, Ordering::Relaxed);
+	}
+
+	/// Adds the errors reported by the line status register `lsr`.
+	pub(crate) fn add_errors(&self, lsr: u8) {
+		[
+			(0x02, Counter::OverrunErrors),
+			(0x04, Counter::ParityErrors),
+			(0x08, Counter::FramingErrors),
+			(0x10, Counter::BreakInterrupts),
+		]
+		.iter()
+		.filter(|&&(bit, _)| lsr & bit != 0)
+		.for_each(|&(_, counter)| self.add(counter, 1));
+	}
+}
+
+#[cfg(all(feature = "stats", target_has_atomic = "ptr"))]
+impl<R: RegisterAccess> Uart<R> {
+	/// Returns the statistics collected since the creation of the instance or the last call to
+	/// [`reset_stats`](Self::reset_stats).
+	pub fn stats(&self) -> Stats {
+		let count = |counter: Counter| self.stats.counts[counter as usize].load(Ordering::Relaxed);
+		Stats {
+			tx_bytes: count(Counter::TxBytes),
+			rx_bytes: count(Counter::RxBytes),
+			overrun_errors: count(Counter::OverrunErrors),
+			parity_errors: count(Counter::ParityErrors),
+			framing_errors: count(Counter::FramingErrors),
+			break_interrupts: count(Counter::BreakInterrupts),
+			dropped_bytes: count(Counter::DroppedBytes),
+		}
+	}
+
+	/// Resets the statistics.
+	pub fn reset_stats(&self) {
+		self.stats
+			.counts
+			.iter()
+			.for_each(|count| count.store(0, Ordering::Relaxed));
+	}
+}
@@ -0,0 +1,330 @@
+//! Recording and replay of register access traces.
+
+use crate::{Register, RegisterAccess};
+use core::cell::{Cell, Ref, RefCell};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Register access, as recorded in a trace
+pub enum Access {
+	/// Value read in a register
+	Read(Register, u8),
+	/// 32 bits value read in a register wider than 8 bits
+	ReadU32(Register, u32),
+	/// Value written in a register
+	Write(Register, u8),
+}
+
+#[derive(Debug)]
+/// Register access recording every access made through `R` in a trace of at most `N` accesses
+pub struct TraceRecorder<R, const N: usize = 256> {
+	regs: R,
+	trace: RefCell<[Access; N]>,
+	len: Cell<usize>,
+	truncated: Cell<bool>,
+}
+
+impl<R: RegisterAccess, const N: usize> TraceRecorder<R, N> {
+	/// Creates a new instance of `TraceRecorder` accessing the registers through `regs`.
+	pub const fn new(regs: R) -> Self {
+		Self {
+			regs,
+			trace: RefCell::new([Access::Read(Register::RBR, 0); N]),
+			len: Cell::new(0),
+			truncated: Cell::new(false),
+		}
+	}
+
+	/// Returns the accesses recorded, oldest first.
+	pub fn trace(&self) -> Ref<'_, [Access]> {
+		Ref::map(self.trace.borrow(), |trace| &trace[..self.len.get()])
+	}
+
+	/// Returns whether accesses were not recorded because the trace was full.
+	pub fn is_truncated(&self) -> bool {
+		self.truncated.get()
+	}
+
+	/// Clears the trace.
+	pub fn clear(&self) {
+		self.len.set(0);
+		self.truncated.set(false);
+	}
+
+	fn record(&self, access: Access) {
+		let len = self.len.get();
+		if len == N {
+			self.truncated.set(true);
+		} else {
+			self.trace.borrow_mut()[len] = access;
+			self.len.set(len + 1);
+		}
+	}
+}
+
+impl<R: RegisterAccess, const N: usize> RegisterAccess for TraceRecorder<R, N> {
+	fn read(&self, reg: Register) -> u8 {
+		let value = self.regs.read(reg);
+		self.record(Access::Read(reg, value));
+		value
+	}
+
+	fn read_u32(&self, reg: Register) -> u32 {
+		let value = self.regs.read_u32(reg);
+		self.record(Access::ReadU32(reg, value));
+		value
+	}
+
+	fn write(&self, reg: Register, value: u8) {
+		self.regs.write(reg, value);
+		self.record(Access::Write(reg, value));
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Error returned by [`TraceReplayer::finish`]
+pub enum ReplayError {
+	/// The access at `index` differs from the trace. For reads, only the register is compared.
+	Mismatch {
+		/// Position of the access in the trace
+		index: usize,
+		/// Access made
+		access: Access,
+	},
+	/// The accesses after `index` in the trace were not made
+	Incomplete {
+		/// Number of accesses made
+		index: usize,
+	},
+}
+
+#[derive(Debug)]
+/// Register access replaying a trace, e.g. recorded by [`TraceRecorder`]
+///
+/// Reads return the values of the trace, and writes are checked against it. Once an access
+/// differs from the trace, reads return 0 and the following accesses are ignored.
+pub struct TraceReplayer<'a> {
+	trace: &'a [Access],
+	index: Cell<usize>,
+	mismatch: Cell<Option<Access>>,
+}
+
+impl<'a> TraceReplayer<'a> {
+	/// Creates a new instance of `TraceReplayer` replaying `trace`.
+	pub const fn new(trace: &'a [Access]) -> Self {
+		Self {
+			trace,
+			index: Cell::new(0),
+			mismatch: Cell::new(None),
+		}
+	}
+
+	/// Returns an error if an access differed from the trace or if the trace was not replayed
+	/// entirely.
+	pub fn finish(&self) -> Result<(), ReplayError> {
+		let index = self.index.get();
+		match self.mismatch.get() {
+			Some(access) => Err(ReplayError::Mismatch { index, access }),
+			None if index != self.trace.len() => Err(ReplayError::Incomplete { index }),
+			None => Ok(()),
+		}
+	}
+
+	/// Returns the value read if `access` matches the next access of the trace, and moves to
+	/// the following one.
+	fn replay(&self, access: Access) -> Option<u32> {
+		if self.mismatch.get().is_some() {
+			return None;
+		}
+		let index = self.index.get();
+		let value = match (self.trace.get(index), access) {
+			(Some(&Access::Read(expected, value)), Access::Read(reg, _)) if expected == reg => {
+				Some(value as u32)
+			}
+			(Some(&Access::ReadU32(expected, value)), Access::ReadU32(reg, _))
+				if expected == reg =>
+			{
+				Some(value)
+			}
+			(Some(&expected), Access::Write(..)) if expected == access => Some(0),
+			_ => None,
+		};
+		match value {
+			Some(_) => self.index.set(index + 1),
+			None => self.mismatch.set(Some(access)),
+		}
+		value
+	}
+}
+
+impl RegisterAccess for TraceReplayer<'_> {
+	fn read(&self, reg: Register) -> u8 {
+		self.replay(Access::Read(reg, 0)).unwrap_or(0) as u8
+	}
+
+	fn read_u32(&self, reg: Register) -> u32 {
+		self.replay(Access::ReadU32(reg, 0)).unwrap_or(0)
+	}
+
+	fn write(&self, reg: Register, value: u8) {
+		self.replay(Access::Write(reg, value));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Config, MockRegisters, Newline, Rs485, Uart};
+	use core::fmt::Write;
+
+	/// Accesses of `init_with` with a divisor of 1, on a peripheral with FIFOs
+	#[cfg(not(feature = "dw-apb"))]
+	const INIT: [Access; 6] = [
+		Access::Write(Register::LCR, 0x83),
+		Access::Write(Register::FCR, 0x01),
+		Access::Write(Register::DLL, 0x01),
+		Access::Write(Register::DLM, 0x00),
+		Access::Write(Register::LCR, 0x03),
+		Access::Read(Register::IIR, 0xC1),
+	];
+	/// Accesses of `init_with` with a divisor of 1, on a peripheral with FIFOs, whose line
+	/// control register writes are checked
+	#[cfg(feature = "dw-apb")]
+	const INIT: [Access; 8] = [
+		Access::Write(Register::LCR, 0x83),
+		Access::Read(Register::LCR, 0x83),
+		Access::Write(Register::FCR, 0x01),
+		Access::Write(Register::DLL, 0x01),
+		Access::Write(Register::DLM, 0x00),
+		Access::Write(Register::LCR, 0x03),
+		Access::Read(Register::LCR, 0x03),
+		Access::Read(Register::IIR, 0xC1),
+	];
+
+	fn init(uart: &Uart<impl RegisterAccess>) {
+		uart.init_with(&Config::new().divisor(1));
+	}
+
+	#[test]
+	fn record_init() {
+		let recorder = TraceRecorder::<_, 16>::new(MockRegisters::new());
+		init(&Uart::with_access(&recorder));
+		assert_eq!(*recorder.trace(), INIT);
+		assert!(!recorder.is_truncated());
+	}
+
+	#[test]
+	fn replay_init() {
+		let replayer = TraceReplayer::new(&INIT);
+		init(&Uart::with_access(&replayer));
+		assert_eq!(replayer.finish(), Ok(()));
+	}
+
+	#[test]
+	fn replay_init_16450() {
+		// The interrupt identification register does not report the FIFOs as enabled, so they
+		// are disabled again
+		let mut trace = [Access::Write(Register::FCR, 0x00); INIT.len() + 1];
+		trace[..INIT.len()].copy_from_slice(&INIT);
+		trace[INIT.len() - 1] = Access::Read(Register::IIR, 0x01);
+		let replayer = TraceReplayer::new(&trace);
+		let uart = Uart::with_access(&replayer);
+		init(&uart);
+		assert_eq!(replayer.finish(), Ok(()));
+		assert_eq!(uart.fifo_depth(), 1);
+	}
+
+	#[test]
+	fn lossy_writer_fills_fifo() {
+		let mut trace = [Access::Read(Register::LSR, 0x00); INIT.len() + 9];
+		trace[..INIT.len()].copy_from_slice(&INIT);
+		let lossy = &mut trace[INIT.len()..];
+		lossy[0] = Access::Read(Register::LSR, 0x60);
+		for (access, &c) in lossy[1..5].iter_mut().zip(b"x = ") {
+			*access = Access::Write(Register::THR, c);
+		}
+		// The transmitter holding register is no longer empty for the following pieces
+		lossy[6] = Access::Write(Register::THR, b'5');
+		lossy[8] = Access::Write(Register::THR, b'!');
+		let replayer = TraceReplayer::new(&trace);
+		let uart = Uart::with_access(&replayer);
+		init(&uart);
+		let mut writer = uart.lossy_writer();
+		write!(writer, "x = {}!", core::hint::black_box(5)).unwrap();
+		assert_eq!(writer.dropped(), 0);
+		assert_eq!(replayer.finish(), Ok(()));
+	}
+
+	#[test]
+	fn rs485_translated_text() {
+		let recorder = TraceRecorder::<_, 32>::new(MockRegisters::new());
+		let mut uart = Uart::with_access(&recorder);
+		uart.set_newline(Newline {
+			lf_to_crlf: true,
+			..Newline::NONE
+		});
+		uart.set_rs485(Some(Rs485 { rts_on_send: true }));
+		recorder.clear();
+		write!(uart, "a\nb").unwrap();
+		assert!(!recorder.is_truncated());
+		let trace = recorder.trace();
+		// Request to send is held once around the whole string
+		let rts = trace.iter().filter_map(|access| match access {
+			Access::Write(Register::MCR, mcr) => Some(mcr & 0x02 != 0),
+			_ => None,
+		});
+		assert!(rts.eq([true, false]));
+		let thr = trace.iter().filter_map(|access| match access {
+			Access::Write(Register::THR, c) => Some(*c),
+			_ => None,
+		});
+		assert!(thr.eq(*b"a\r\nb"));
+	}
+
+	/// Register access whose registers other than the line control register are read as 0,
+	/// so that its enhanced features register is readable
+	#[derive(Default)]
+	struct Zeros {
+		lcr: Cell<u8>,
+	}
+
+	impl RegisterAccess for Zeros {
+		fn read(&self, reg: Register) -> u8 {
+			match reg {
+				Register::LCR => self.lcr.get(),
+				_ => 0,
+			}
+		}
+
+		fn write(&self, reg: Register, value: u8) {
+			if reg == Register::LCR {
+				self.lcr.set(value);
+			}
+		}
+	}
+
+	#[test]
+	fn ox16c950_id_keeps_acr() {
+		let recorder = TraceRecorder::<_, 64>::new(Zeros::default());
+		let mut uart = Uart::with_access(&recorder);
+		uart.capabilities.fifo128 = true;
+		uart.set_trigger_levels(8, 8);
+		recorder.clear();
+		assert!(!uart.has_ox16c950_id(0x03));
+		assert!(!recorder.is_truncated());
+		// The additional control register is written for each of the 3 identification
+		// registers, and restored after each of them
+		let trace = recorder.trace();
+		let acr = trace.windows(2).filter_map(|accesses| match accesses {
+			[Access::Write(Register::SCR, 0x00), Access::Write(Register::ICR, acr)] => Some(*acr),
+			_ => None,
+		});
+		assert!(acr.eq([0x60, 0x20, 0x60, 0x20, 0x60, 0x20]));
+	}
+
+	#[test]
+	fn record_read_u32() {
+		let recorder = TraceRecorder::<_, 1>::new(MockRegisters::new());
+		assert_eq!(recorder.read_u32(Register::CPR), 0);
+		assert_eq!(*recorder.trace(), [Access::ReadU32(Register::CPR, 0)]);
+	}
+}
@@ -0,0 +1,56 @@
+//! Wait strategies used by the blocking operations.
+//!
+//! Each time a blocking operation polls the peripheral and finds it not ready, it calls
+//! [`WaitStrategy::wait`] before polling again. The default, [`Spin`], busy-waits. Any
+//! `Fn() + Sync`, e.g. a function yielding to the scheduler or feeding a watchdog, can be used
+//! as a strategy too.
+
+/// Strategy called by the blocking operations between two polls of the peripheral
+pub trait WaitStrategy: Sync {
+	/// Waits before the peripheral is polled again
+	fn wait(&self);
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Busy-waits, hinting the processor that it is in a spin loop
+pub struct Spin;
+
+impl WaitStrategy for Spin {
+	fn wait(&self) {
+		core::hint::spin_loop();
+	}
+}
+
+#[cfg(any(
+	target_arch = "riscv32",
+	target_arch = "riscv64",
+	target_arch = "arm",
+	target_arch = "aarch64"
+))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Halts the processor with `wfi` until an interrupt is pending
+///
+/// A pending interrupt resumes execution even if it is masked, but the UART interrupt must be
+/// enabled for the processor to resume when the peripheral becomes ready.
+pub struct Wfi;
+
+#[cfg(any(
+	target_arch = "riscv32",
+	target_arch = "riscv64",
+	target_arch = "arm",
+	target_arch = "aarch64"
+))]
+impl WaitStrategy for Wfi {
+	fn wait(&self) {
+		// Not `nomem`, as the interrupt handlers may modify memory before `wfi` returns
+		unsafe {
+			core::arch::asm!("wfi", options(nostack, preserves_flags));
+		}
+	}
+}
+
+impl<F: Fn() + Sync> WaitStrategy for F {
+	fn wait(&self) {
+		self();
+	}
+}